@@ -0,0 +1,671 @@
+use git2::Repository;
+use glob::glob;
+use log::debug;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::path::Path;
+use toml::{Table, Value};
+
+use crate::functions::is_tracked;
+use crate::manifest::{pending_update, set_toml_version, FileUpdate};
+
+/// Tables declaring dependencies, also nested in `target.<cfg>` and `workspace`.
+const DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Matches a TOML table or array of tables header, e.g. `[dependencies]` or `[[bin]]`.
+const TABLE_HEADER_REGEX: &str = r"^\s*\[{1,2}([^\[\]]+)\]{1,2}\s*(?:#.*)?$";
+
+/// Matches the header of a table declaring a single dependency, e.g. `[dev-dependencies.core]`.
+const DEPENDENCY_TABLE_REGEX: &str =
+    r#"^(?:.*\.)?(?:dev-|build-)?dependencies\.("?)([A-Za-z0-9_-]+)"?$"#;
+
+/// Matches a dependency declared as inline table, e.g. `core = { path = "../core" }`.
+const INLINE_DEPENDENCY_REGEX: &str = r#"^\s*"?([A-Za-z0-9_-]+)"?\s*=\s*\{.*\}"#;
+
+/// Matches a version requirement of a dependency.
+const REQUIREMENT_REGEX: &str = r#"(\bversion\s*=\s*)(["'])([^"']*)(["'])"#;
+
+/// A package of a Cargo workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CargoPackage {
+    /// Name of the package
+    pub name: String,
+    /// Path of the manifest relative to the repository root
+    pub manifest: String,
+    /// Current version, None if the manifest does not declare one
+    pub version: Option<String>,
+    /// Whether the version is inherited from `[workspace.package]`
+    pub inherited: bool,
+    /// Names of the packages this package depends on by path, excluding dev-dependencies
+    pub dependencies: Vec<String>,
+}
+
+/// A Cargo workspace, or a single package outside of any workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CargoWorkspace {
+    /// Path of the root manifest relative to the repository root
+    pub manifest: String,
+    /// Packages of the workspace, including the root package
+    pub packages: Vec<CargoPackage>,
+}
+
+impl CargoWorkspace {
+    /// Find the workspace the given manifest belongs to by looking for a `[workspace]` listing it
+    /// in the parent directories. A manifest outside of any workspace is its own workspace.
+    ///
+    /// * `work_dir`: Root of the repository's working directory
+    /// * `manifest`: Path of a package or workspace manifest relative to the working directory
+    pub fn find(work_dir: &Path, manifest: &str) -> Result<Self, String> {
+        let mut dir = parent_dir(manifest);
+        loop {
+            let candidate = join_path(dir, "Cargo.toml");
+            if work_dir.join(&candidate).is_file() {
+                let table = read_manifest(work_dir, &candidate)?;
+                if table.contains_key("workspace") {
+                    let workspace = CargoWorkspace::load(work_dir, &candidate, &table)?;
+                    if candidate == manifest
+                        || workspace.packages.iter().any(|p| p.manifest == manifest)
+                    {
+                        debug!("{} belongs to the workspace {}", manifest, candidate);
+                        return Ok(workspace);
+                    }
+                }
+            }
+            if dir.is_empty() {
+                break;
+            }
+            dir = parent_dir(dir);
+        }
+
+        let table = read_manifest(work_dir, manifest)?;
+        CargoWorkspace::load(work_dir, manifest, &table)
+    }
+
+    /// Load the packages of a workspace from its root manifest, expanding the member globs.
+    fn load(work_dir: &Path, manifest: &str, table: &Table) -> Result<Self, String> {
+        let workspace = table.get("workspace").and_then(Value::as_table);
+        let dir = parent_dir(manifest);
+
+        let mut packages = Vec::new();
+        if table.contains_key("package") {
+            packages.push(read_package(manifest, table, workspace)?);
+        }
+        let strings = |key: &str| -> Vec<String> {
+            workspace
+                .and_then(|workspace| workspace.get(key))
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(|path| join_path(dir, path.trim_end_matches('/')))
+                .collect()
+        };
+        let excluded = strings("exclude");
+        for member in strings("members") {
+            let pattern = work_dir.join(&member);
+            let paths = glob(&pattern.to_string_lossy())
+                .map_err(|e| format!("Invalid workspace member {}: {}", member, e))?;
+            for path in paths.flatten() {
+                let Ok(relative) = path.strip_prefix(work_dir) else {
+                    continue;
+                };
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                let member_manifest = join_path(&relative, "Cargo.toml");
+                if excluded.contains(&relative)
+                    || !work_dir.join(&member_manifest).is_file()
+                    || packages.iter().any(|p| p.manifest == member_manifest)
+                {
+                    continue;
+                }
+                let member_table = read_manifest(work_dir, &member_manifest)?;
+                packages.push(read_package(&member_manifest, &member_table, workspace)?);
+            }
+        }
+
+        Ok(CargoWorkspace {
+            manifest: manifest.to_owned(),
+            packages,
+        })
+    }
+
+    /// Set the versions of the given packages. Updates the package manifests or the
+    /// `[workspace.package]` version, the requirements of path dependencies on the packages in all
+    /// manifests of the workspace and the entries in `Cargo.lock`. Packages inheriting the
+    /// workspace version are always bumped together. `Cargo.lock` is only updated if it is
+    /// tracked in HEAD.
+    ///
+    /// * `repo`: Repository of the workspace
+    /// * `updates`: Pending file updates, extended by the changed files
+    /// * `versions`: New versions by package name
+    pub fn set_versions(
+        &self,
+        repo: &Repository,
+        updates: &mut Vec<FileUpdate>,
+        versions: &BTreeMap<String, String>,
+    ) -> Result<(), String> {
+        let work_dir = repo
+            .workdir()
+            .ok_or("Manifests cannot be updated in a bare repository.")?;
+        let mut versions = versions.clone();
+        let inherited = self.packages.iter().filter(|package| package.inherited);
+        let workspace_version = inherited
+            .clone()
+            .find_map(|package| versions.get(&package.name))
+            .cloned();
+        if let Some(version) = &workspace_version {
+            for package in inherited {
+                if versions.get(&package.name).is_some_and(|v| v != version) {
+                    return Err(format!(
+                        "Packages inheriting the version of {} cannot be bumped to different versions.",
+                        self.manifest
+                    ));
+                }
+                versions.insert(package.name.clone(), version.clone());
+            }
+            let update = pending_update(work_dir, updates, &self.manifest)?;
+            update.new = set_toml_version(&update.new, &["workspace.package"], version)
+                .ok_or_else(|| format!("No workspace version found in {}.", self.manifest))?;
+        }
+
+        let mut bumped = Vec::new();
+        for package in &self.packages {
+            let Some(version) = versions.get(&package.name) else {
+                continue;
+            };
+            let Some(old) = &package.version else {
+                return Err(format!("{} has no version.", package.manifest));
+            };
+            if !package.inherited {
+                let update = pending_update(work_dir, updates, &package.manifest)?;
+                update.new = set_toml_version(&update.new, &["package"], version)
+                    .ok_or_else(|| format!("No version found in {}.", package.manifest))?;
+            }
+            debug!("Bumping {} from {} to {}", package.name, old, version);
+            bumped.push((package.name.as_str(), old.as_str(), version.as_str()));
+        }
+
+        let manifests = std::iter::once(&self.manifest)
+            .chain(self.packages.iter().map(|package| &package.manifest));
+        for manifest in manifests {
+            let update = pending_update(work_dir, updates, manifest)?;
+            update.new = set_dependency_versions(&update.new, &versions);
+        }
+
+        let lockfile = join_path(parent_dir(&self.manifest), "Cargo.lock");
+        if is_tracked(repo, &lockfile) {
+            let update = pending_update(work_dir, updates, &lockfile)?;
+            update.new = set_lock_versions(&update.new, &bumped);
+        }
+        Ok(())
+    }
+}
+
+/// Bump the packages of the given Cargo manifests to the version, along with the dependency
+/// requirements and lockfile entries of their workspaces. A workspace root manifest selects all
+/// packages of the workspace.
+///
+/// * `repo`: Repository of the manifests
+/// * `updates`: Pending file updates, extended by the changed files
+/// * `manifests`: Paths of Cargo manifests relative to the working directory
+/// * `version`: New version without tag prefix
+pub fn bump_manifests(
+    repo: &Repository,
+    updates: &mut Vec<FileUpdate>,
+    manifests: &[String],
+    version: &str,
+) -> Result<(), String> {
+    let work_dir = repo
+        .workdir()
+        .ok_or("Manifests cannot be updated in a bare repository.")?;
+    let mut workspaces: Vec<(CargoWorkspace, BTreeMap<String, String>)> = Vec::new();
+    for manifest in manifests {
+        let workspace = CargoWorkspace::find(work_dir, manifest)?;
+        let selected: Vec<String> = workspace
+            .packages
+            .iter()
+            .filter(|package| workspace.manifest == *manifest || package.manifest == *manifest)
+            .map(|package| package.name.clone())
+            .collect();
+        if selected.is_empty() {
+            return Err(format!("{} declares no package.", manifest));
+        }
+
+        let index = match workspaces
+            .iter()
+            .position(|(w, _)| w.manifest == workspace.manifest)
+        {
+            Some(index) => index,
+            None => {
+                workspaces.push((workspace, BTreeMap::new()));
+                workspaces.len() - 1
+            }
+        };
+        for name in selected {
+            workspaces[index].1.insert(name, version.to_owned());
+        }
+    }
+
+    for (workspace, versions) in &workspaces {
+        workspace.set_versions(repo, updates, versions)?;
+    }
+    Ok(())
+}
+
+/// Read and parse a Cargo manifest.
+fn read_manifest(work_dir: &Path, manifest: &str) -> Result<Table, String> {
+    let content = std::fs::read_to_string(work_dir.join(manifest))
+        .map_err(|e| format!("Could not read {}: {}", manifest, e))?;
+    content
+        .parse::<Table>()
+        .map_err(|e| format!("Invalid manifest {}: {}", manifest, e))
+}
+
+/// Read the `[package]` table and path dependencies of a parsed manifest.
+///
+/// * `manifest`: Path of the manifest
+/// * `table`: Parsed manifest
+/// * `workspace`: `[workspace]` table of the workspace root manifest
+fn read_package(
+    manifest: &str,
+    table: &Table,
+    workspace: Option<&Table>,
+) -> Result<CargoPackage, String> {
+    let workspace_package = workspace.and_then(|workspace| workspace.get("package"));
+    let workspace_version = workspace_package
+        .and_then(|package| package.get("version"))
+        .and_then(Value::as_str);
+    let workspace_dependencies = workspace
+        .and_then(|workspace| workspace.get("dependencies"))
+        .and_then(Value::as_table);
+
+    let package = table
+        .get("package")
+        .and_then(Value::as_table)
+        .ok_or_else(|| format!("{} declares no package.", manifest))?;
+    let name = package
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("No package name in {}.", manifest))?;
+    let (version, inherited) = match package.get("version") {
+        Some(Value::String(version)) => (Some(version.clone()), false),
+        Some(Value::Table(table)) if table.get("workspace") == Some(&Value::Boolean(true)) => {
+            (workspace_version.map(str::to_owned), true)
+        }
+        _ => (None, false),
+    };
+
+    // Dependency tables of the package, including platform specific ones
+    let targets = table
+        .get("target")
+        .and_then(Value::as_table)
+        .into_iter()
+        .flat_map(|targets| targets.values().filter_map(Value::as_table));
+    let mut dependencies = Vec::new();
+    for tables in std::iter::once(table).chain(targets) {
+        let declared = ["dependencies", "build-dependencies"]
+            .iter()
+            .filter_map(|key| tables.get(*key).and_then(Value::as_table))
+            .flatten();
+        for (key, dependency) in declared {
+            // Dependencies with `workspace = true` are declared in `[workspace.dependencies]`
+            let dependency = match dependency.get("workspace").and_then(Value::as_bool) {
+                Some(true) => workspace_dependencies.and_then(|deps| deps.get(key)),
+                _ => Some(dependency),
+            };
+            let Some(dependency) = dependency.and_then(Value::as_table) else {
+                continue;
+            };
+            if dependency.contains_key("path") {
+                let name = dependency.get("package").and_then(Value::as_str);
+                dependencies.push(name.unwrap_or(key).to_owned());
+            }
+        }
+    }
+
+    Ok(CargoPackage {
+        name: name.to_owned(),
+        manifest: manifest.to_owned(),
+        version,
+        inherited,
+        dependencies,
+    })
+}
+
+/// Rewrite the version requirements of path dependencies on the given packages, preserving the
+/// requirement operator and formatting. Dependencies renamed with `package` are matched by their
+/// package name.
+fn set_dependency_versions(content: &str, versions: &BTreeMap<String, String>) -> String {
+    // Safety: Regexes are verified to be valid
+    let header = Regex::new(TABLE_HEADER_REGEX).unwrap();
+    let single = Regex::new(DEPENDENCY_TABLE_REGEX).unwrap();
+    let inline = Regex::new(INLINE_DEPENDENCY_REGEX).unwrap();
+    let package = Regex::new(r#"\bpackage\s*=\s*["']([^"']*)["']"#).unwrap();
+    let path = Regex::new(r"\bpath\s*=").unwrap();
+
+    // Split the manifest into tables, the first one holds the lines before any header
+    let mut tables: Vec<(String, String)> = vec![(String::new(), String::new())];
+    for line in content.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\r', '\n']);
+        match header.captures(text) {
+            Some(captures) => tables.push((captures[1].trim().to_owned(), line.to_owned())),
+            // Safety: There is always at least one table
+            None => tables.last_mut().unwrap().1.push_str(line),
+        }
+    }
+
+    let rewrite = |text: &str, key: &str| -> Option<String> {
+        let name = package
+            .captures(text)
+            .map(|captures| captures[1].to_owned())
+            .unwrap_or_else(|| key.to_owned());
+        let version = versions.get(&name)?;
+        if !path.is_match(text) {
+            return None;
+        }
+        Some(replace_requirement(text, version))
+    };
+
+    let mut updated = String::with_capacity(content.len());
+    for (table, body) in tables {
+        let is_list = DEPENDENCY_TABLES
+            .iter()
+            .any(|name| table == *name || table.ends_with(&format!(".{}", name)));
+        if is_list {
+            for line in body.split_inclusive('\n') {
+                let rewritten = inline
+                    .captures(line)
+                    .and_then(|captures| rewrite(line, &captures[1]));
+                updated.push_str(rewritten.as_deref().unwrap_or(line));
+            }
+        } else if let Some(captures) = single.captures(&table) {
+            updated.push_str(&rewrite(&body, &captures[2]).unwrap_or(body));
+        } else {
+            updated.push_str(&body);
+        }
+    }
+    updated
+}
+
+/// Replace the first version requirement in the text by one for the given version.
+fn replace_requirement(text: &str, version: &str) -> String {
+    // Safety: Regex is verified to be valid
+    let re = Regex::new(REQUIREMENT_REGEX).unwrap();
+    re.replacen(text, 1, |captures: &regex::Captures| {
+        format!(
+            "{}{}{}{}",
+            &captures[1],
+            &captures[2],
+            bump_requirement(&captures[3], version),
+            &captures[4]
+        )
+    })
+    .into_owned()
+}
+
+/// Update a version requirement to the given version, keeping its operator. Ranges and wildcards
+/// are kept as they are.
+fn bump_requirement(requirement: &str, version: &str) -> String {
+    let trimmed = requirement.trim();
+    if trimmed.contains([',', '*', '<', '>']) {
+        debug!("Keeping version requirement {}", requirement);
+        return requirement.to_owned();
+    }
+    let operator = trimmed.len() - trimmed.trim_start_matches(['=', '^', '~']).len();
+    format!("{}{}", &trimmed[..operator], version)
+}
+
+/// Update the versions of local packages in a lockfile, including references to them in the
+/// dependencies of other packages.
+///
+/// * `content`: Content of `Cargo.lock`
+/// * `bumped`: Name, old and new version of each bumped package
+fn set_lock_versions(content: &str, bumped: &[(&str, &str, &str)]) -> String {
+    // Safety: Regex is verified to be valid
+    let name_re = Regex::new(r#"(?m)^name = "([^"]*)""#).unwrap();
+
+    let mut blocks: Vec<String> = vec![String::new()];
+    for line in content.split_inclusive('\n') {
+        if line.trim_end() == "[[package]]" {
+            blocks.push(String::new());
+        }
+        // Safety: There is always at least one block
+        blocks.last_mut().unwrap().push_str(line);
+    }
+
+    let mut updated = String::with_capacity(content.len());
+    for mut block in blocks {
+        let name = name_re
+            .captures(&block)
+            .map(|captures| captures[1].to_owned());
+        // Packages from registries or git repositories have a source, local ones do not
+        let local = !block.contains("\nsource = ");
+        for (package, old, new) in bumped {
+            if local && name.as_deref() == Some(package) {
+                block = block.replacen(
+                    &format!("\nversion = \"{}\"", old),
+                    &format!("\nversion = \"{}\"", new),
+                    1,
+                );
+            }
+            block = block.replace(
+                &format!("\"{} {}\"", package, old),
+                &format!("\"{} {}\"", package, new),
+            );
+        }
+        updated.push_str(&block);
+    }
+    updated
+}
+
+/// Directory of a path relative to the repository root, empty for the root itself.
+fn parent_dir(path: &str) -> &str {
+    path.rsplit_once('/')
+        .map(|(dir, _)| dir)
+        .unwrap_or_default()
+}
+
+/// Join a directory relative to the repository root and a path.
+fn join_path(dir: &str, path: &str) -> String {
+    match (dir, path) {
+        ("", path) | (path, ".") => path.to_owned(),
+        (dir, path) => format!("{}/{}", dir, path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit, init_repo};
+    use tempfile::TempDir;
+
+    const LOCKFILE: &str = r#"# This file is automatically @generated by Cargo.
+version = 3
+
+[[package]]
+name = "cli"
+version = "0.3.0"
+dependencies = [
+ "core",
+ "serde",
+]
+
+[[package]]
+name = "core"
+version = "0.1.0"
+
+[[package]]
+name = "serde"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    /// Create a repository with a workspace of a `core` package inheriting the workspace version
+    /// and a `cli` package with its own version depending on it. The lockfile is only committed if
+    /// `lockfile` is true, otherwise it is ignored.
+    fn workspace(lockfile: bool) -> (TempDir, Repository) {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let write = |path: &str, content: &str| {
+            let file = dir.path().join(path);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, content).unwrap();
+        };
+        write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/experimental\"]\n\n\
+             [workspace.package]\nversion = \"0.1.0\"\n\n\
+             [workspace.dependencies]\ncore = { path = \"crates/core\", version = \"0.1.0\" }\n\
+             serde = \"0.1.0\"\n",
+        );
+        write(
+            "crates/core/Cargo.toml",
+            "[package]\nname = \"core\"\nversion.workspace = true\n",
+        );
+        write(
+            "crates/cli/Cargo.toml",
+            "[package]\nname = \"cli\"\nversion = \"0.3.0\"\n\n\
+             [dependencies]\ncore = { workspace = true }\nserde = { version = \"0.1.0\" }\n\n\
+             [dev-dependencies.core]\npath = \"../core\"\nversion = \"=0.1.0\"\n",
+        );
+        write(
+            "crates/experimental/Cargo.toml",
+            "[package]\nname = \"experimental\"\nversion = \"0.0.1\"\n",
+        );
+        write("Cargo.lock", LOCKFILE);
+        if !lockfile {
+            write(".gitignore", "/Cargo.lock\n");
+        }
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        commit(&repo, "chore: workspace");
+        (dir, repo)
+    }
+
+    fn content<'u>(updates: &'u [FileUpdate], path: &str) -> &'u str {
+        &updates
+            .iter()
+            .find(|update| update.path == path)
+            .unwrap()
+            .new
+    }
+
+    #[test]
+    fn test_find_workspace() {
+        let (dir, _repo) = workspace(true);
+
+        let workspace = CargoWorkspace::find(dir.path(), "crates/cli/Cargo.toml").unwrap();
+        assert_eq!(workspace.manifest, "Cargo.toml");
+        let mut packages: Vec<(&str, Option<&str>, bool, Vec<String>)> = workspace
+            .packages
+            .iter()
+            .map(|p| {
+                let dependencies = p.dependencies.clone();
+                (
+                    p.name.as_str(),
+                    p.version.as_deref(),
+                    p.inherited,
+                    dependencies,
+                )
+            })
+            .collect();
+        packages.sort();
+        assert_eq!(
+            packages,
+            vec![
+                ("cli", Some("0.3.0"), false, vec!["core".to_owned()]),
+                ("core", Some("0.1.0"), true, vec![]),
+            ]
+        );
+
+        let excluded = CargoWorkspace::find(dir.path(), "crates/experimental/Cargo.toml").unwrap();
+        assert_eq!(excluded.manifest, "crates/experimental/Cargo.toml");
+    }
+
+    #[test]
+    fn test_bump_workspace() {
+        let (_dir, repo) = workspace(true);
+        let mut updates = Vec::new();
+
+        bump_manifests(&repo, &mut updates, &["Cargo.toml".to_owned()], "0.4.0").unwrap();
+
+        assert_eq!(
+            content(&updates, "Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/experimental\"]\n\n\
+             [workspace.package]\nversion = \"0.4.0\"\n\n\
+             [workspace.dependencies]\ncore = { path = \"crates/core\", version = \"0.4.0\" }\n\
+             serde = \"0.1.0\"\n"
+        );
+        assert_eq!(
+            content(&updates, "crates/cli/Cargo.toml"),
+            "[package]\nname = \"cli\"\nversion = \"0.4.0\"\n\n\
+             [dependencies]\ncore = { workspace = true }\nserde = { version = \"0.1.0\" }\n\n\
+             [dev-dependencies.core]\npath = \"../core\"\nversion = \"=0.4.0\"\n"
+        );
+        assert_eq!(
+            content(&updates, "Cargo.lock"),
+            LOCKFILE
+                .replace(
+                    "\"cli\"\nversion = \"0.3.0\"",
+                    "\"cli\"\nversion = \"0.4.0\""
+                )
+                .replace(
+                    "\"core\"\nversion = \"0.1.0\"",
+                    "\"core\"\nversion = \"0.4.0\""
+                )
+        );
+    }
+
+    #[test]
+    fn test_bump_single_member() {
+        let (_dir, repo) = workspace(true);
+        let mut updates = Vec::new();
+
+        bump_manifests(
+            &repo,
+            &mut updates,
+            &["crates/cli/Cargo.toml".to_owned()],
+            "0.3.1",
+        )
+        .unwrap();
+
+        let changed: Vec<&str> = updates
+            .iter()
+            .filter(|update| update.old != update.new)
+            .map(|update| update.path.as_str())
+            .collect();
+        assert_eq!(changed, vec!["crates/cli/Cargo.toml", "Cargo.lock"]);
+        assert!(content(&updates, "Cargo.lock").contains("\"cli\"\nversion = \"0.3.1\""));
+    }
+
+    #[test]
+    fn test_bump_ignored_lockfile() {
+        let (_dir, repo) = workspace(false);
+        let mut updates = Vec::new();
+
+        bump_manifests(&repo, &mut updates, &["Cargo.toml".to_owned()], "0.4.0").unwrap();
+
+        assert!(updates.iter().all(|update| update.path != "Cargo.lock"));
+        assert!(updates.iter().any(|update| update.path == "Cargo.toml"));
+    }
+
+    #[test]
+    fn test_bump_requirement() {
+        assert_eq!(bump_requirement("0.1", "0.2.0"), "0.2.0");
+        assert_eq!(bump_requirement("=0.1.0", "0.2.0"), "=0.2.0");
+        assert_eq!(bump_requirement("~0.1.0", "0.2.0"), "~0.2.0");
+        assert_eq!(bump_requirement(">=0.1, <0.3", "0.2.0"), ">=0.1, <0.3");
+    }
+
+    #[test]
+    fn test_lock_dependency_with_version() {
+        let lockfile = "[[package]]\nname = \"app\"\nversion = \"1.0.0\"\ndependencies = [\n \"core 0.1.0\",\n \"core 0.9.0 (registry+https://github.com/rust-lang/crates.io-index)\",\n]\n";
+
+        assert_eq!(
+            set_lock_versions(lockfile, &[("core", "0.1.0", "0.2.0")]),
+            lockfile.replace("\"core 0.1.0\"", "\"core 0.2.0\"")
+        );
+    }
+}
@@ -0,0 +1,499 @@
+use regex::Regex;
+use std::path::Path;
+
+use crate::config::{CommitParser, Config};
+use crate::conventional::ConventionalCommit;
+use crate::manifest::{pending_update, FileUpdate};
+
+/// Matches the heading of the unreleased section, e.g. `## [Unreleased]`.
+const UNRELEASED_REGEX: &str = r"(?im)^## \[?unreleased\]?.*$";
+
+/// Matches the heading of any release section.
+const SECTION_REGEX: &str = r"(?m)^## ";
+
+/// Beginning of a newly created changelog.
+const CHANGELOG_HEADER: &str = "# Changelog\n\n";
+
+/// Changelog entries of a group, e.g. all features of a release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangelogGroup {
+    /// Title of the group
+    pub title: String,
+    /// Entries in the order of the commits
+    pub entries: Vec<String>,
+}
+
+/// Compiled rules preprocessing, filtering and grouping commit messages for the changelog and the
+/// inference of the version bump.
+pub struct CommitRules {
+    /// Regexes with their replacement applied to messages before parsing
+    preprocessors: Vec<(Regex, String)>,
+    /// Parsers with their compiled message and body regexes
+    parsers: Vec<(Option<Regex>, Option<Regex>, CommitParser)>,
+    /// Whether unconventional commits are left out of the changelog
+    filter_unconventional: bool,
+}
+
+impl CommitRules {
+    /// Compile the commit rules of the given configuration.
+    pub fn new(config: &Config) -> Result<Self, String> {
+        let compile = |regex: &Option<String>| {
+            regex
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| format!("Invalid commit parser regex: {}", e))
+        };
+        let parsers = config
+            .commit_parsers()
+            .into_iter()
+            .map(|parser| Ok((compile(&parser.message)?, compile(&parser.body)?, parser)))
+            .collect::<Result<Vec<_>, String>>()?;
+        let preprocessors = config
+            .commit_preprocessors
+            .iter()
+            .flatten()
+            .map(|preprocessor| {
+                Regex::new(&preprocessor.pattern)
+                    .map(|re| (re, preprocessor.replace.clone()))
+                    .map_err(|e| format!("Invalid commit preprocessor regex: {}", e))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(CommitRules {
+            preprocessors,
+            parsers,
+            filter_unconventional: config.filter_unconventional.unwrap_or(true),
+        })
+    }
+
+    /// Apply the preprocessors to a commit message.
+    pub fn preprocess(&self, message: &str) -> String {
+        self.preprocessors
+            .iter()
+            .fold(message.to_owned(), |message, (re, replace)| {
+                re.replace_all(&message, replace.as_str()).into_owned()
+            })
+    }
+
+    /// The first parser whose message or body regex matches the preprocessed message.
+    fn parser(&self, message: &str) -> Option<&CommitParser> {
+        let body = message.split_once('\n').map(|(_, body)| body.trim());
+        self.parsers
+            .iter()
+            .find(|(message_regex, body_regex, _)| {
+                message_regex
+                    .as_ref()
+                    .is_some_and(|re| re.is_match(message))
+                    || body_regex
+                        .as_ref()
+                        .is_some_and(|re| body.is_some_and(|body| re.is_match(body)))
+            })
+            .map(|(_, _, parser)| parser)
+    }
+
+    /// Returns true if a parser skips the preprocessed message.
+    pub fn skips(&self, message: &str) -> bool {
+        self.parser(message).is_some_and(|parser| parser.skip)
+    }
+
+    /// Group commits by the first parser matching their message or body. Groups are ordered like
+    /// the parsers defining them. Commits no parser assigns a group and, unless configured
+    /// otherwise, unconventional commits are left out.
+    ///
+    /// * `messages`: Full commit messages, oldest first
+    pub fn group_commits(&self, messages: &[String]) -> Vec<ChangelogGroup> {
+        let mut groups: Vec<ChangelogGroup> = Vec::new();
+        for message in messages {
+            let message = self.preprocess(message);
+            let entry = match ConventionalCommit::parse(&message) {
+                Some(commit) if commit.breaking => {
+                    format!("[**breaking**] {}", upper_first(&commit.description))
+                }
+                Some(commit) => upper_first(&commit.description),
+                None if self.filter_unconventional => continue,
+                None => upper_first(message.lines().next().unwrap_or_default().trim()),
+            };
+            let Some(title) = self
+                .parser(&message)
+                .filter(|parser| !parser.skip)
+                .and_then(|parser| parser.group.as_ref())
+            else {
+                continue;
+            };
+
+            match groups.iter_mut().find(|group| &group.title == title) {
+                Some(group) => group.entries.push(entry),
+                None => groups.push(ChangelogGroup {
+                    title: title.clone(),
+                    entries: vec![entry],
+                }),
+            }
+        }
+
+        let position = |title: &str| {
+            self.parsers
+                .iter()
+                .position(|(_, _, parser)| parser.group.as_deref() == Some(title))
+        };
+        groups.sort_by_key(|group| position(&group.title));
+        groups
+    }
+}
+
+/// Render the section of a release in Keep a Changelog style.
+///
+/// * `version`: Version of the release without tag prefix
+/// * `date`: Date of the release as `YYYY-MM-DD`
+/// * `groups`: Grouped changelog entries
+pub fn render_section(version: &str, date: &str, groups: &[ChangelogGroup]) -> String {
+    let mut section = format!("## [{}] - {}\n", version, date);
+    for group in groups {
+        section.push_str(&format!("\n### {}\n\n", group.title));
+        for entry in &group.entries {
+            section.push_str(&format!("- {}\n", entry));
+        }
+    }
+    section
+}
+
+/// Add the section of a release to a changelog. An existing unreleased section is promoted to the
+/// release, its entries are kept and the generated ones merged into it. Otherwise the section is
+/// inserted above the last release.
+///
+/// * `content`: Current content of the changelog, empty if it does not exist yet
+/// * `version`: Version of the release without tag prefix
+/// * `date`: Date of the release as `YYYY-MM-DD`
+/// * `groups`: Grouped changelog entries
+pub fn prepend_release(
+    content: &str,
+    version: &str,
+    date: &str,
+    groups: &[ChangelogGroup],
+) -> String {
+    // Safety: Regexes are verified to be valid
+    let unreleased_re = Regex::new(UNRELEASED_REGEX).unwrap();
+    let section_re = Regex::new(SECTION_REGEX).unwrap();
+    let section = render_section(version, date, groups);
+
+    if let Some(heading) = unreleased_re.find(content) {
+        let end = section_re
+            .find_at(content, heading.end())
+            .map_or(content.len(), |next| next.start());
+        if content[heading.end()..end].trim().is_empty() {
+            let separator = if end < content.len() { "\n" } else { "" };
+            return format!(
+                "{}{}{}{}",
+                &content[..heading.start()],
+                section,
+                separator,
+                &content[end..]
+            );
+        }
+        return format!(
+            "{}## [{}] - {}{}{}",
+            &content[..heading.start()],
+            version,
+            date,
+            merge_groups(&content[heading.end()..end], groups),
+            &content[end..]
+        );
+    }
+
+    match section_re.find(content) {
+        _ if content.trim().is_empty() => format!("{}{}", CHANGELOG_HEADER, section),
+        Some(last_release) => format!(
+            "{}{}\n{}",
+            &content[..last_release.start()],
+            section,
+            &content[last_release.start()..]
+        ),
+        None => format!("{}\n\n{}", content.trim_end(), section),
+    }
+}
+
+/// Merge generated entries into the body of an existing section. Entries are appended to the
+/// subsection of their group, or to a new subsection at the end. Entries already listed are left
+/// out.
+///
+/// * `body`: Body of the section below its heading
+/// * `groups`: Grouped changelog entries
+fn merge_groups(body: &str, groups: &[ChangelogGroup]) -> String {
+    // Safety: Regex is verified to be valid
+    let subsection_re = Regex::new(r"(?m)^### ").unwrap();
+    let mut merged = body.trim_end().to_owned();
+    for group in groups {
+        let entries: Vec<String> = group
+            .entries
+            .iter()
+            .map(|entry| format!("- {}", entry))
+            .filter(|entry| !merged.lines().any(|line| line.trim_end() == entry))
+            .collect();
+        if entries.is_empty() {
+            continue;
+        }
+
+        // Safety: The title is escaped
+        let heading_re =
+            Regex::new(&format!(r"(?m)^### {}[ \t]*$", regex::escape(&group.title))).unwrap();
+        match heading_re.find(&merged) {
+            Some(heading) => {
+                let end = subsection_re
+                    .find_at(&merged, heading.end())
+                    .map_or(merged.len(), |next| next.start());
+                let position = merged[..end].trim_end().len();
+                merged.insert_str(position, &format!("\n{}", entries.join("\n")));
+            }
+            None => merged.push_str(&format!(
+                "\n\n### {}\n\n{}",
+                group.title,
+                entries.join("\n")
+            )),
+        }
+    }
+    merged.push_str(&body[body.trim_end().len()..]);
+    merged
+}
+
+/// Add the section of a release to the changelog at the given path, which is created if it does
+/// not exist.
+///
+/// * `work_dir`: Root of the repository's working directory
+/// * `updates`: Pending file updates
+/// * `path`: Path of the changelog relative to the working directory
+/// * `version`: Version of the release without tag prefix
+/// * `date`: Date of the release as `YYYY-MM-DD`
+/// * `groups`: Grouped changelog entries
+pub fn update_changelog(
+    work_dir: &Path,
+    updates: &mut Vec<FileUpdate>,
+    path: &str,
+    version: &str,
+    date: &str,
+    groups: &[ChangelogGroup],
+) -> Result<(), String> {
+    let exists = work_dir.join(path).is_file() || updates.iter().any(|u| u.path == path);
+    let update = match exists {
+        true => pending_update(work_dir, updates, path)?,
+        false => {
+            updates.push(FileUpdate {
+                path: path.to_owned(),
+                old: String::new(),
+                new: String::new(),
+            });
+            // Safety: An update was just added
+            updates.last_mut().unwrap()
+        }
+    };
+    update.new = prepend_release(&update.new, version, date, groups);
+    Ok(())
+}
+
+/// Turn the first character of the given text to upper case.
+fn upper_first(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CommitPreprocessor;
+    use crate::functions::{checkout_release_commit, create_release_commit};
+    use crate::test_utils::init_repo;
+    use tempfile::TempDir;
+
+    fn groups() -> Vec<ChangelogGroup> {
+        vec![ChangelogGroup {
+            title: "Features".to_owned(),
+            entries: vec!["Add --changelog".to_owned()],
+        }]
+    }
+
+    #[test]
+    fn test_group_commits() {
+        let messages: Vec<String> = [
+            "fix: crash on empty repo",
+            "feat(cli): add --changelog",
+            "Update README",
+            "chore(release): 1.2.0",
+            "docs: typo",
+            "feat!: drop lightweight tags",
+            "fix: escape input\n\nPrevents a security issue",
+        ]
+        .iter()
+        .map(|message| message.to_string())
+        .collect();
+        let mut parsers = Config::default().commit_parsers();
+        parsers.insert(
+            0,
+            CommitParser {
+                message: None,
+                body: Some("security".to_owned()),
+                group: Some("Security".to_owned()),
+                skip: false,
+            },
+        );
+        let config = Config {
+            commit_parsers: Some(parsers),
+            ..Config::default()
+        };
+
+        let groups = CommitRules::new(&config).unwrap().group_commits(&messages);
+        let summary: Vec<(&str, Vec<&str>)> = groups
+            .iter()
+            .map(|g| {
+                (
+                    g.title.as_str(),
+                    g.entries.iter().map(|e| e.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Security", vec!["Escape input"]),
+                (
+                    "Features",
+                    vec!["Add --changelog", "[**breaking**] Drop lightweight tags"]
+                ),
+                ("Bug Fixes", vec!["Crash on empty repo"]),
+                ("Documentation", vec!["Typo"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_preprocess_unconventional() {
+        let config = Config {
+            commit_parsers: Some(vec![CommitParser {
+                message: Some("^(feat|Add)".to_owned()),
+                body: None,
+                group: Some("Added".to_owned()),
+                skip: false,
+            }]),
+            commit_preprocessors: Some(vec![CommitPreprocessor {
+                pattern: r"\(#([0-9]+)\)".to_owned(),
+                replace: "([#$1](https://example.com/$1))".to_owned(),
+            }]),
+            filter_unconventional: Some(false),
+            ..Config::default()
+        };
+        let rules = CommitRules::new(&config).unwrap();
+        let messages = vec!["feat: tags (#12)".to_owned(), "Add flag".to_owned()];
+
+        assert_eq!(
+            rules.group_commits(&messages),
+            vec![ChangelogGroup {
+                title: "Added".to_owned(),
+                entries: vec![
+                    "Tags ([#12](https://example.com/12))".to_owned(),
+                    "Add flag".to_owned()
+                ],
+            }]
+        );
+        assert!(!rules.skips("feat: tags"));
+        assert!(CommitRules::new(&Config::default())
+            .unwrap()
+            .skips("chore(release): 1.2.0"));
+    }
+
+    #[test]
+    fn test_prepend_new_changelog() {
+        assert_eq!(
+            prepend_release("", "1.2.0", "2023-10-01", &groups()),
+            "# Changelog\n\n## [1.2.0] - 2023-10-01\n\n### Features\n\n- Add --changelog\n"
+        );
+    }
+
+    #[test]
+    fn test_prepend_above_last_release() {
+        let content = "# Changelog\n\nAll notable changes.\n\n## [1.1.0] - 2023-09-01\n\n- Old\n";
+
+        assert_eq!(
+            prepend_release(content, "1.2.0", "2023-10-01", &groups()),
+            "# Changelog\n\nAll notable changes.\n\n## [1.2.0] - 2023-10-01\n\n### Features\n\n\
+             - Add --changelog\n\n## [1.1.0] - 2023-09-01\n\n- Old\n"
+        );
+    }
+
+    #[test]
+    fn test_promote_empty_unreleased() {
+        let content = "# Changelog\n\n## [Unreleased]\n\n## [1.1.0] - 2023-09-01\n";
+
+        assert_eq!(
+            prepend_release(content, "1.2.0", "2023-10-01", &groups()),
+            "# Changelog\n\n## [1.2.0] - 2023-10-01\n\n### Features\n\n- Add --changelog\n\n\
+             ## [1.1.0] - 2023-09-01\n"
+        );
+    }
+
+    #[test]
+    fn test_promote_unreleased_entries() {
+        let content = "# Changelog\n\n## [unreleased]\n\n### Added\n\n- Curated\n\n## [1.1.0]\n";
+
+        assert_eq!(
+            prepend_release(content, "1.2.0", "2023-10-01", &groups()),
+            "# Changelog\n\n## [1.2.0] - 2023-10-01\n\n### Added\n\n- Curated\n\n\
+             ### Features\n\n- Add --changelog\n\n## [1.1.0]\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_into_unreleased_groups() {
+        let content = "## [Unreleased]\n\n### Features\n\n- Curated\n- Add --changelog\n\n\
+                       ### Bug Fixes\n\n- Fixed by hand\n";
+        let groups = vec![
+            ChangelogGroup {
+                title: "Features".to_owned(),
+                entries: vec!["Add --changelog".to_owned(), "Add --push".to_owned()],
+            },
+            ChangelogGroup {
+                title: "Bug Fixes".to_owned(),
+                entries: vec!["Crash".to_owned()],
+            },
+        ];
+
+        assert_eq!(
+            prepend_release(content, "1.2.0", "2023-10-01", &groups),
+            "## [1.2.0] - 2023-10-01\n\n### Features\n\n- Curated\n- Add --changelog\n\
+             - Add --push\n\n### Bug Fixes\n\n- Fixed by hand\n- Crash\n"
+        );
+    }
+
+    #[test]
+    fn test_release_commit_creates_changelog() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let mut updates = Vec::new();
+
+        update_changelog(
+            dir.path(),
+            &mut updates,
+            "CHANGELOG.md",
+            "0.1.0",
+            "2023-10-01",
+            &[],
+        )
+        .unwrap();
+        assert_eq!(updates[0].old, "");
+        assert_eq!(updates[0].new, "# Changelog\n\n## [0.1.0] - 2023-10-01\n");
+
+        let release = create_release_commit(&repo, &updates, "chore(release): 0.1.0").unwrap();
+        checkout_release_commit(&repo, &release, &updates).unwrap();
+        let blob = release
+            .tree()
+            .unwrap()
+            .get_name("CHANGELOG.md")
+            .unwrap()
+            .to_object(&repo)
+            .unwrap()
+            .peel_to_blob()
+            .unwrap();
+        assert_eq!(blob.content(), updates[0].new.as_bytes());
+        assert!(dir.path().join("CHANGELOG.md").is_file());
+    }
+}
@@ -0,0 +1,228 @@
+use log::{debug, warn};
+use std::path::Path;
+use toml::{Table, Value};
+
+use crate::config::{CommitParser, CommitPreprocessor, Config};
+
+/// Name of the git-cliff configuration file in the repository root.
+pub const CLIFF_CONFIG_FILE: &str = "cliff.toml";
+
+/// Parse the `[git]` section of a git-cliff configuration. Returns the equivalent taggr
+/// configuration and a warning for every setting taggr does not implement.
+///
+/// * `content`: Content of `cliff.toml`
+pub fn parse_cliff_config(content: &str) -> Result<(Config, Vec<String>), String> {
+    let table: Table = toml::from_str(content).map_err(|e| e.to_string())?;
+    let mut config = Config::default();
+    let mut warnings = Vec::new();
+    let Some(git) = table.get("git") else {
+        return Ok((config, warnings));
+    };
+    let git = git.as_table().ok_or("git must be a table.")?;
+
+    for (key, value) in git {
+        match key.as_str() {
+            "commit_parsers" => {
+                config.commit_parsers = Some(commit_parsers(value, &mut warnings)?);
+            }
+            "commit_preprocessors" => {
+                config.commit_preprocessors = Some(commit_preprocessors(value, &mut warnings)?);
+            }
+            "tag_pattern" => config.tag_pattern = non_empty(key, value)?,
+            "skip_tags" => config.skip_tags = non_empty(key, value)?,
+            "ignore_tags" => config.exclude_tags = non_empty(key, value)?,
+            "filter_unconventional" => {
+                let filter = value
+                    .as_bool()
+                    .ok_or("git.filter_unconventional must be a boolean.")?;
+                config.filter_unconventional = Some(filter);
+            }
+            _ if is_compatible(key, value) => debug!("git.{} matches the behavior of taggr", key),
+            _ => warnings.push(format!(
+                "git.{} = {} is not supported, ignoring it.",
+                key, value
+            )),
+        }
+    }
+    config.validate()?;
+    Ok((config, warnings))
+}
+
+/// Read the git-cliff configuration at the given path and log warnings about unsupported
+/// settings. Returns None if the file does not exist.
+pub fn read_cliff_config(path: &Path) -> Result<Option<Config>, String> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    debug!("Reading git-cliff configuration from {}", path.display());
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let (config, warnings) = parse_cliff_config(&content)
+        .map_err(|e| format!("Invalid configuration in {}: {}", path.display(), e))?;
+    for warning in warnings {
+        warn!("{}: {}", path.display(), warning);
+    }
+    Ok(Some(config))
+}
+
+/// Returns true if the given setting of the `[git]` section describes what taggr does anyway.
+fn is_compatible(key: &str, value: &Value) -> bool {
+    match key {
+        "conventional_commits" => value.as_bool() == Some(true),
+        "filter_commits" => value.as_bool() == Some(true),
+        "split_commits" | "date_order" | "protect_breaking_commits" => {
+            value.as_bool() == Some(false)
+        }
+        "sort_commits" => value.as_str() == Some("oldest"),
+        _ => false,
+    }
+}
+
+/// Read an optional string setting, treating an empty string as unset.
+fn non_empty(key: &str, value: &Value) -> Result<Option<String>, String> {
+    let value = value
+        .as_str()
+        .ok_or_else(|| format!("git.{} must be a string.", key))?;
+    Ok(Some(value.to_owned()).filter(|value| !value.is_empty()))
+}
+
+/// Convert the commit parsers of git-cliff. Parsers relying on unsupported keys only are dropped.
+fn commit_parsers(value: &Value, warnings: &mut Vec<String>) -> Result<Vec<CommitParser>, String> {
+    let mut parsers = Vec::new();
+    for parser in tables(value, "commit_parsers")? {
+        let string = |key: &str| parser.get(key).and_then(Value::as_str).map(str::to_owned);
+        for key in parser.keys() {
+            if !["message", "body", "group", "skip"].contains(&key.as_str()) {
+                warnings.push(format!(
+                    "commit parser key {} is not supported, ignoring it.",
+                    key
+                ));
+            }
+        }
+        let parser = CommitParser {
+            message: string("message"),
+            body: string("body"),
+            group: string("group"),
+            skip: parser.get("skip").and_then(Value::as_bool).unwrap_or(false),
+        };
+        match (&parser.message, &parser.body) {
+            (None, None) => warnings.push(format!(
+                "commit parser of group {} has no message or body regex, ignoring it.",
+                parser.group.as_deref().unwrap_or("none")
+            )),
+            _ => parsers.push(parser),
+        }
+    }
+    Ok(parsers)
+}
+
+/// Convert the commit preprocessors of git-cliff. Preprocessors running a command are dropped.
+fn commit_preprocessors(
+    value: &Value,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<CommitPreprocessor>, String> {
+    let mut preprocessors = Vec::new();
+    for preprocessor in tables(value, "commit_preprocessors")? {
+        let string = |key: &str| preprocessor.get(key).and_then(Value::as_str);
+        let Some(pattern) = string("pattern") else {
+            return Err("Commit preprocessors need a pattern.".to_owned());
+        };
+        match string("replace") {
+            Some(replace) => preprocessors.push(CommitPreprocessor {
+                pattern: pattern.to_owned(),
+                replace: replace.to_owned(),
+            }),
+            None => warnings.push(format!(
+                "commit preprocessor {} has no replace string, ignoring it.",
+                pattern
+            )),
+        }
+    }
+    Ok(preprocessors)
+}
+
+/// The tables of an array setting.
+fn tables<'v>(value: &'v Value, key: &str) -> Result<Vec<&'v Table>, String> {
+    value
+        .as_array()
+        .and_then(|array| array.iter().map(Value::as_table).collect())
+        .ok_or_else(|| format!("git.{} must be an array of tables.", key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_git_section() {
+        let (config, warnings) = parse_cliff_config(
+            r##"
+            [changelog]
+            header = "# Changelog"
+
+            [git]
+            conventional_commits = true
+            filter_unconventional = false
+            commit_preprocessors = [
+                { pattern = '\((\w+\s)?#([0-9]+)\)', replace = "([#${2}](https://example.com/${2}))" },
+            ]
+            commit_parsers = [
+                { message = "^feat", group = "Features" },
+                { message = "^chore\\(release\\): prepare for", skip = true },
+                { body = ".*security", group = "Security" },
+            ]
+            tag_pattern = "v[0-9]*"
+            skip_tags = "v0.1.0-beta.1"
+            ignore_tags = ""
+            sort_commits = "oldest"
+            "##,
+        )
+        .unwrap();
+
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(config.commit_parsers.as_ref().unwrap().len(), 3);
+        assert!(config.commit_parsers.as_ref().unwrap()[1].skip);
+        assert_eq!(
+            config.commit_preprocessors.unwrap()[0].replace,
+            "([#${2}](https://example.com/${2}))"
+        );
+        assert_eq!(config.tag_pattern.as_deref(), Some("v[0-9]*"));
+        assert_eq!(config.skip_tags.as_deref(), Some("v0.1.0-beta.1"));
+        assert_eq!(config.exclude_tags, None);
+        assert_eq!(config.filter_unconventional, Some(false));
+    }
+
+    #[test]
+    fn test_warn_unsupported() {
+        let (config, warnings) = parse_cliff_config(
+            r#"
+            [git]
+            split_commits = true
+            filter_commits = false
+            commit_parsers = [
+                { message = "^feat", scope = "app", group = "Features" },
+                { sha = "f6f2472", skip = true },
+            ]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.commit_parsers.unwrap().len(), 1);
+        assert_eq!(warnings.len(), 5, "{:?}", warnings);
+        assert!(warnings
+            .contains(&"git.split_commits = true is not supported, ignoring it.".to_owned()));
+    }
+
+    #[test]
+    fn test_parse_without_git_section() {
+        let (config, warnings) = parse_cliff_config("[changelog]\ntrim = true").unwrap();
+
+        assert_eq!(config, Config::default());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_parse_invalid_regex() {
+        assert!(parse_cliff_config("[git]\nskip_tags = '('").is_err());
+    }
+}
@@ -0,0 +1,810 @@
+use glob::{MatchOptions, Pattern};
+use log::debug;
+use regex::Regex;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::cliff::{read_cliff_config, CLIFF_CONFIG_FILE};
+use crate::elements::{TagFilter, TagSelection, Type, Version};
+
+/// Name of the project configuration file in the repository root.
+pub const PROJECT_CONFIG_FILE: &str = ".taggr.toml";
+
+/// Branches releases may be tagged on if nothing else is configured and the default branch of the
+/// repository cannot be determined.
+pub const DEFAULT_RELEASE_BRANCHES: [&str; 2] = ["master", "main"];
+
+/// Glob matching options for branch names, `*` does not match across `/` while `**` does.
+const BRANCH_MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Describe pattern matching tags that contain a semantic version.
+pub const DEFAULT_TAG_PATTERN: &str = "*[0-9]*.[0-9]*.[0-9]*";
+
+/// Changelog groups used if no commit parsers are configured, as message regex and group. A
+/// parser without group skips the matching commits.
+const DEFAULT_COMMIT_PARSERS: [(&str, Option<&str>); 10] = [
+    ("^feat", Some("Features")),
+    ("^fix", Some("Bug Fixes")),
+    ("^doc", Some("Documentation")),
+    ("^perf", Some("Performance")),
+    ("^refactor", Some("Refactor")),
+    ("^style", Some("Styling")),
+    ("^test", Some("Testing")),
+    (r"^chore\(release\)", None),
+    ("^chore", Some("Miscellaneous Tasks")),
+    ("^ci", Some("Continuous Integration")),
+];
+
+/// A release branch, either given as plain glob pattern or as table with a policy restricting the
+/// releases that may be tagged on matching branches.
+///
+/// # Example
+/// ```toml
+/// release_branches = [
+///     "main",
+///     "release/*",
+///     { pattern = "support/*", bumps = ["patch"] },
+///     { pattern = "develop", prerelease_only = true },
+/// ]
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "BranchPolicyDef")]
+pub struct BranchPolicy {
+    /// Glob pattern of branch names the policy applies to
+    pub pattern: String,
+    /// Version bumps allowed on the branch, all if None
+    pub bumps: Option<Vec<Type>>,
+    /// Whether only pre-release versions may be tagged on the branch
+    pub prerelease_only: bool,
+}
+
+/// Serialized forms of a `BranchPolicy`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BranchPolicyDef {
+    Pattern(String),
+    Policy {
+        pattern: String,
+        #[serde(default)]
+        bumps: Option<Vec<Type>>,
+        #[serde(default)]
+        prerelease_only: bool,
+    },
+}
+
+impl From<BranchPolicyDef> for BranchPolicy {
+    fn from(def: BranchPolicyDef) -> Self {
+        match def {
+            BranchPolicyDef::Pattern(pattern) => BranchPolicy::new(&pattern),
+            BranchPolicyDef::Policy {
+                pattern,
+                bumps,
+                prerelease_only,
+            } => BranchPolicy {
+                pattern,
+                bumps,
+                prerelease_only,
+            },
+        }
+    }
+}
+
+impl BranchPolicy {
+    /// Create a policy allowing any release on branches matching the pattern.
+    pub fn new(pattern: &str) -> Self {
+        BranchPolicy {
+            pattern: pattern.to_owned(),
+            bumps: None,
+            prerelease_only: false,
+        }
+    }
+
+    /// Returns true if the given branch name matches the pattern of the policy.
+    pub fn matches(&self, branch: &str) -> bool {
+        Pattern::new(&self.pattern)
+            .map(|pattern| pattern.matches_with(branch, BRANCH_MATCH_OPTIONS))
+            .unwrap_or(false)
+    }
+
+    /// Check whether the given release may be tagged on a branch of this policy.
+    ///
+    /// * `bump`: Applied bump, None for the first release of a repository
+    /// * `version`: New version
+    pub fn check(&self, bump: Option<Type>, version: &Version) -> Result<(), String> {
+        if let (Some(bumps), Some(bump)) = (&self.bumps, bump) {
+            if !bumps.contains(&bump) {
+                let allowed: Vec<String> = bumps.iter().map(|b| b.to_string()).collect();
+                return Err(format!(
+                    "{} bumps are not allowed on {} branches, only {}.",
+                    bump,
+                    self.pattern,
+                    allowed.join(", ")
+                ));
+            }
+        }
+        if self.prerelease_only && !version.is_prerelease() {
+            return Err(format!(
+                "Only pre-releases may be tagged on {} branches, {} is a release.",
+                self.pattern, version
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A version replacement in an arbitrary file. All matches of the regex are replaced by the
+/// template, which supports the placeholders `{version}` (without tag prefix) and `{tag}` as well
+/// as references to capture groups like `$1` or `${name}`.
+///
+/// # Example
+/// ```toml
+/// [[replacements]]
+/// file = "Dockerfile"
+/// search_regex = 'LABEL version="[^"]*"'
+/// replace_template = 'LABEL version="{version}"'
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Replacement {
+    /// Path of the file relative to the repository root
+    pub file: String,
+    /// Regex matching the text to replace
+    pub search_regex: String,
+    /// Replacement of each match
+    pub replace_template: String,
+}
+
+/// A rule assigning commits to a group of the changelog, like the `commit_parsers` of git-cliff.
+/// The first parser whose message or body regex matches a commit decides its group.
+///
+/// # Example
+/// ```toml
+/// commit_parsers = [
+///     { message = "^feat", group = "Features" },
+///     { message = "^chore\\(release\\)", skip = true },
+///     { body = "security", group = "Security" },
+/// ]
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommitParser {
+    /// Regex matched against the whole commit message
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Regex matched against the commit body
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Changelog group of matching commits
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Whether matching commits are left out of the changelog
+    #[serde(default)]
+    pub skip: bool,
+}
+
+/// A regex replacement applied to commit messages before they are parsed, like the
+/// `commit_preprocessors` of git-cliff. The replacement supports references to capture groups
+/// like `$1` or `${name}`.
+///
+/// # Example
+/// ```toml
+/// commit_preprocessors = [
+///     { pattern = '\(#([0-9]+)\)', replace = "([#${1}](https://example.com/issues/${1}))" },
+/// ]
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommitPreprocessor {
+    /// Regex matched against the commit message
+    pub pattern: String,
+    /// Replacement of each match
+    pub replace: String,
+}
+
+/// A component of a monorepo, released independently with its own tag prefix and version stream.
+///
+/// # Example
+/// ```toml
+/// [[components]]
+/// name = "api"
+/// tag_prefix = "api/v"
+/// paths = ["services/api/", "proto/*.proto"]
+/// manifests = ["services/api/Cargo.toml"]
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Component {
+    /// Name of the component, selected with `--component`
+    pub name: String,
+    /// Prefix of the component's tags, defaults to `<name>/v`
+    #[serde(default)]
+    pub tag_prefix: Option<String>,
+    /// Git pathspecs of the files owned by the component, the whole repository if empty
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Manifests whose version is updated when tagging the component
+    #[serde(default)]
+    pub manifests: Option<Vec<String>>,
+    /// Version replacements applied when tagging the component
+    #[serde(default)]
+    pub replacements: Option<Vec<Replacement>>,
+}
+
+impl Component {
+    /// Create a component using the default tag prefix.
+    pub fn new(name: &str) -> Self {
+        Component {
+            name: name.to_owned(),
+            tag_prefix: None,
+            paths: Vec::new(),
+            manifests: None,
+            replacements: None,
+        }
+    }
+
+    /// Prefix of the component's tags.
+    pub fn tag_prefix(&self) -> String {
+        self.tag_prefix
+            .clone()
+            .unwrap_or_else(|| format!("{}/v", self.name))
+    }
+}
+
+/// Settings read from `.taggr.toml` in the repository root or the user configuration in
+/// `$XDG_CONFIG_HOME/taggr/config.toml`. Unset values fall back to the built-in defaults.
+///
+/// # Example
+/// ```toml
+/// release_branches = ["main", "release/*"]
+/// tag_prefix = "v"
+/// message = "Release {version}\n\n{commits}"
+/// sign = true
+/// push = "origin"
+/// bump = "patch"
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Branches releases may be tagged on
+    pub release_branches: Option<Vec<BranchPolicy>>,
+    /// Prefix of new tags, e.g. `v`
+    pub tag_prefix: Option<String>,
+    /// Glob pattern of tags considered when looking up the last version
+    pub tag_pattern: Option<String>,
+    /// Regex tags have to match to be considered when looking up the last version
+    pub include_tags: Option<String>,
+    /// Regex of tags to skip when looking up the last version
+    pub exclude_tags: Option<String>,
+    /// Regex of tags that are neither used as last version nor included in the changelog
+    pub skip_tags: Option<String>,
+    /// Strategy to select the tag of the last version
+    pub tag_selection: Option<TagSelection>,
+    /// Template of the tag message
+    pub message: Option<String>,
+    /// Whether to sign tags, overrides `tag.gpgSign`
+    pub sign: Option<bool>,
+    /// Remote to push new tags to
+    pub push: Option<String>,
+    /// Version element to bump if none is selected
+    pub bump: Option<Type>,
+    /// Components of a monorepo with their own version streams
+    pub components: Option<Vec<Component>>,
+    /// Manifests whose version is updated in a release commit before tagging
+    pub manifests: Option<Vec<String>>,
+    /// Version replacements in arbitrary files, committed along with the manifests
+    pub replacements: Option<Vec<Replacement>>,
+    /// Template of the release commit message
+    pub commit_message: Option<String>,
+    /// Changelog the release notes are prepended to in the release commit
+    pub changelog: Option<String>,
+    /// Rules grouping commits in the changelog
+    pub commit_parsers: Option<Vec<CommitParser>>,
+    /// Replacements applied to commit messages before they are parsed
+    pub commit_preprocessors: Option<Vec<CommitPreprocessor>>,
+    /// Whether commits not following the Conventional Commits specification are left out of the
+    /// changelog
+    pub filter_unconventional: Option<bool>,
+}
+
+impl Config {
+    /// Parse a configuration from TOML.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let config: Config = toml::from_str(content).map_err(|e| e.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check that the patterns and regexes of the configuration are valid and that component
+    /// names are unique.
+    pub fn validate(&self) -> Result<(), String> {
+        for policy in self.release_branches.iter().flatten() {
+            Pattern::new(&policy.pattern)
+                .map_err(|e| format!("Invalid branch pattern {}: {}", policy.pattern, e))?;
+        }
+        let components = self.components.iter().flatten();
+        for (i, component) in components.clone().enumerate() {
+            if component.name.is_empty() {
+                return Err("Component names must not be empty.".to_owned());
+            }
+            if components.clone().take(i).any(|c| c.name == component.name) {
+                return Err(format!("Duplicate component {}.", component.name));
+            }
+        }
+        let replacements = self.replacements.iter().flatten().chain(
+            components
+                .clone()
+                .flat_map(|component| component.replacements.iter().flatten()),
+        );
+        for replacement in replacements {
+            Regex::new(&replacement.search_regex)
+                .map_err(|e| format!("Invalid search_regex for {}: {}", replacement.file, e))?;
+        }
+        for parser in self.commit_parsers.iter().flatten() {
+            if parser.message.is_none() && parser.body.is_none() {
+                return Err("Commit parsers need a message or body regex.".to_owned());
+            }
+            compile_regex("commit parser message", &parser.message)?;
+            compile_regex("commit parser body", &parser.body)?;
+        }
+        for preprocessor in self.commit_preprocessors.iter().flatten() {
+            Regex::new(&preprocessor.pattern)
+                .map_err(|e| format!("Invalid commit preprocessor regex: {}", e))?;
+        }
+        self.tag_filter()?;
+        Ok(())
+    }
+
+    /// Read a configuration file. Returns None if the file does not exist.
+    pub fn read(path: &Path) -> Result<Option<Self>, String> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        debug!("Reading configuration from {}", path.display());
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        Config::parse(&content)
+            .map(Some)
+            .map_err(|e| format!("Invalid configuration in {}: {}", path.display(), e))
+    }
+
+    /// Load the configuration for a repository. Values of the project configuration take
+    /// precedence over the ones of a git-cliff configuration in the repository, which take
+    /// precedence over the ones of the user configuration.
+    ///
+    /// * `work_dir`: Root of the repository's working directory
+    pub fn load(work_dir: Option<&Path>) -> Result<Self, String> {
+        let user = match user_config_path() {
+            Some(path) => Config::read(&path)?.unwrap_or_default(),
+            None => Config::default(),
+        };
+        let (project, cliff) = match work_dir {
+            Some(dir) => (
+                Config::read(&dir.join(PROJECT_CONFIG_FILE))?.unwrap_or_default(),
+                read_cliff_config(&dir.join(CLIFF_CONFIG_FILE))?.unwrap_or_default(),
+            ),
+            None => (Config::default(), Config::default()),
+        };
+        Ok(project.or(cliff).or(user))
+    }
+
+    /// Combine two configurations, values set in `self` take precedence over the ones in `other`.
+    pub fn or(self, other: Config) -> Config {
+        Config {
+            release_branches: self.release_branches.or(other.release_branches),
+            tag_prefix: self.tag_prefix.or(other.tag_prefix),
+            tag_pattern: self.tag_pattern.or(other.tag_pattern),
+            include_tags: self.include_tags.or(other.include_tags),
+            exclude_tags: self.exclude_tags.or(other.exclude_tags),
+            skip_tags: self.skip_tags.or(other.skip_tags),
+            tag_selection: self.tag_selection.or(other.tag_selection),
+            message: self.message.or(other.message),
+            sign: self.sign.or(other.sign),
+            push: self.push.or(other.push),
+            bump: self.bump.or(other.bump),
+            components: self.components.or(other.components),
+            manifests: self.manifests.or(other.manifests),
+            replacements: self.replacements.or(other.replacements),
+            commit_message: self.commit_message.or(other.commit_message),
+            changelog: self.changelog.or(other.changelog),
+            commit_parsers: self.commit_parsers.or(other.commit_parsers),
+            commit_preprocessors: self.commit_preprocessors.or(other.commit_preprocessors),
+            filter_unconventional: self.filter_unconventional.or(other.filter_unconventional),
+        }
+    }
+
+    /// Branches releases may be tagged on. Defaults to the default branch of the repository if
+    /// known, master and main otherwise.
+    ///
+    /// * `default_branch`: Default branch of the repository
+    pub fn release_branches(&self, default_branch: Option<&str>) -> Vec<BranchPolicy> {
+        self.release_branches
+            .clone()
+            .unwrap_or_else(|| match default_branch {
+                Some(branch) => vec![BranchPolicy::new(branch)],
+                None => DEFAULT_RELEASE_BRANCHES
+                    .iter()
+                    .map(|branch| BranchPolicy::new(branch))
+                    .collect(),
+            })
+    }
+
+    /// Look up a configured component by name. Any name is accepted with the default tag prefix
+    /// if no components are configured.
+    ///
+    /// * `name`: Name of the component
+    pub fn component(&self, name: &str) -> Result<Component, String> {
+        match &self.components {
+            Some(components) => components
+                .iter()
+                .find(|component| component.name == name)
+                .cloned()
+                .ok_or_else(|| {
+                    let names: Vec<&str> = components.iter().map(|c| c.name.as_str()).collect();
+                    format!(
+                        "Unknown component {}, configured are: {}.",
+                        name,
+                        names.join(", ")
+                    )
+                }),
+            None => Ok(Component::new(name)),
+        }
+    }
+
+    /// Scope the configuration to the version stream of the given component. The tag prefix,
+    /// manifests and replacements are replaced by the component's ones, `tag_pattern` and
+    /// `include_tags` only apply to the repository's own version stream.
+    ///
+    /// * `component`: Component to scope to
+    pub fn for_component(&self, component: &Component) -> Config {
+        debug!(
+            "Component {} uses tag prefix {}",
+            component.name,
+            component.tag_prefix()
+        );
+        Config {
+            tag_prefix: Some(component.tag_prefix()),
+            tag_pattern: None,
+            include_tags: None,
+            manifests: component.manifests.clone(),
+            replacements: component.replacements.clone(),
+            ..self.clone()
+        }
+    }
+
+    /// Filter of tags considered when looking up the last version. Tags of other components and
+    /// skipped tags are never considered.
+    pub fn tag_filter(&self) -> Result<TagFilter, String> {
+        let excluded_prefixes = self
+            .components
+            .iter()
+            .flatten()
+            .map(|component| component.tag_prefix())
+            .filter(|prefix| self.tag_prefix.as_ref() != Some(prefix))
+            .collect();
+        Ok(TagFilter {
+            include: compile_regex("include_tags", &self.include_tags)?,
+            exclude: compile_regex("exclude_tags", &self.excluded_tags())?,
+            excluded_prefixes,
+            ..TagFilter::new(&self.tag_pattern())
+        })
+    }
+
+    /// Regex of tags not considered as last version, combining `exclude_tags` and `skip_tags`.
+    fn excluded_tags(&self) -> Option<String> {
+        match (&self.exclude_tags, &self.skip_tags) {
+            (Some(exclude), Some(skip)) => Some(format!("(?:{})|(?:{})", exclude, skip)),
+            (exclude, skip) => exclude.clone().or(skip.clone()),
+        }
+    }
+
+    /// Regex of skipped tags, whose commits are left out of the changelog.
+    pub fn skip_tags(&self) -> Result<Option<Regex>, String> {
+        compile_regex("skip_tags", &self.skip_tags)
+    }
+
+    /// Glob pattern of tags considered when looking up the last version. Defaults to tags with
+    /// the configured prefix followed by a version.
+    pub fn tag_pattern(&self) -> String {
+        match (&self.tag_pattern, &self.tag_prefix) {
+            (Some(pattern), _) => pattern.clone(),
+            (None, Some(prefix)) => format!("{}[0-9]*.[0-9]*.[0-9]*", prefix),
+            (None, None) => DEFAULT_TAG_PATTERN.to_owned(),
+        }
+    }
+
+    /// Rules grouping commits in the changelog. Defaults to groups of the Conventional Commits
+    /// types, skipping release commits.
+    pub fn commit_parsers(&self) -> Vec<CommitParser> {
+        self.commit_parsers.clone().unwrap_or_else(|| {
+            DEFAULT_COMMIT_PARSERS
+                .iter()
+                .map(|(message, group)| CommitParser {
+                    message: Some(message.to_string()),
+                    body: None,
+                    group: group.map(str::to_owned),
+                    skip: group.is_none(),
+                })
+                .collect()
+        })
+    }
+}
+
+/// Compile an optional regex from the configuration.
+fn compile_regex(key: &str, regex: &Option<String>) -> Result<Option<Regex>, String> {
+    regex
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| format!("Invalid {} regex: {}", key, e))
+}
+
+/// Location of the user configuration, `$XDG_CONFIG_HOME/taggr/config.toml` falling back to
+/// `~/.config/taggr/config.toml`.
+fn user_config_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("taggr").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_full() {
+        let config = Config::parse(
+            r#"
+            release_branches = ["main", { pattern = "support/*", bumps = ["patch"] }]
+            tag_prefix = "v"
+            tag_selection = "highest-reachable"
+            message = "Release {version}"
+            sign = true
+            push = "origin"
+            bump = "minor"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.release_branches(Some("trunk")),
+            vec![
+                BranchPolicy::new("main"),
+                BranchPolicy {
+                    pattern: "support/*".to_owned(),
+                    bumps: Some(vec![Type::Patch]),
+                    prerelease_only: false,
+                }
+            ]
+        );
+        assert_eq!(config.tag_pattern(), "v[0-9]*.[0-9]*.[0-9]*");
+        assert_eq!(config.tag_selection, Some(TagSelection::HighestReachable));
+        assert_eq!(config.message.as_deref(), Some("Release {version}"));
+        assert_eq!(config.sign, Some(true));
+        assert_eq!(config.push.as_deref(), Some("origin"));
+        assert_eq!(config.bump, Some(Type::Minor));
+    }
+
+    #[test]
+    fn test_parse_empty_uses_defaults() {
+        let config = Config::parse("").unwrap();
+
+        assert_eq!(
+            config.release_branches(None),
+            vec![BranchPolicy::new("master"), BranchPolicy::new("main")]
+        );
+        assert_eq!(
+            config.release_branches(Some("trunk")),
+            vec![BranchPolicy::new("trunk")]
+        );
+        assert_eq!(config.tag_pattern(), DEFAULT_TAG_PATTERN);
+    }
+
+    #[test]
+    fn test_parse_unknown_key() {
+        assert!(Config::parse("relase_branches = [\"main\"]").is_err());
+    }
+
+    #[test]
+    fn test_tag_filter() {
+        let config =
+            Config::parse("include_tags = '^v'\nexclude_tags = '^v0\\.1\\.0-beta\\.1$'").unwrap();
+        let filter = config.tag_filter().unwrap();
+
+        assert_eq!(filter.pattern, DEFAULT_TAG_PATTERN);
+        assert!(filter.matches("v0.1.0"));
+        assert!(!filter.matches("v0.1.0-beta.1"));
+        assert!(!filter.matches("deps/openssl-3.0.8"));
+    }
+
+    #[test]
+    fn test_tag_filter_skip_tags() {
+        let config = Config::parse("exclude_tags = 'nightly'\nskip_tags = 'beta'").unwrap();
+        let filter = config.tag_filter().unwrap();
+
+        assert!(filter.matches("v0.1.0"));
+        assert!(!filter.matches("v0.1.0-beta.1"));
+        assert!(!filter.matches("v0.1.0-nightly"));
+        assert!(config
+            .skip_tags()
+            .unwrap()
+            .unwrap()
+            .is_match("v0.1.0-beta.1"));
+    }
+
+    #[test]
+    fn test_parse_invalid_tag_regex() {
+        assert!(Config::parse("exclude_tags = '(unclosed'").is_err());
+    }
+
+    #[test]
+    fn test_for_component() {
+        let config = Config::parse(
+            r#"
+            tag_prefix = "v"
+            include_tags = "^v"
+
+            [[components]]
+            name = "api"
+
+            [[components]]
+            name = "web"
+            tag_prefix = "frontend-"
+            paths = ["web/"]
+            manifests = ["web/package.json"]
+            "#,
+        )
+        .unwrap();
+
+        let api = config.for_component(&config.component("api").unwrap());
+        assert_eq!(api.tag_prefix.as_deref(), Some("api/v"));
+        assert_eq!(api.tag_pattern(), "api/v[0-9]*.[0-9]*.[0-9]*");
+        let filter = api.tag_filter().unwrap();
+        assert!(filter.matches("api/v1.2.3"));
+        assert!(!filter.matches_prefix("frontend-"));
+
+        let web = config.component("web").unwrap();
+        assert_eq!(web.paths, vec!["web/".to_owned()]);
+        let web = config.for_component(&web);
+        assert_eq!(web.tag_prefix.as_deref(), Some("frontend-"));
+        assert_eq!(web.manifests, Some(vec!["web/package.json".to_owned()]));
+        assert!(web.tag_filter().unwrap().matches_prefix("frontend-"));
+
+        let root = config.tag_filter().unwrap();
+        assert!(!root.matches_prefix("api/v"));
+        assert!(root.matches_prefix("v"));
+
+        assert!(config.component("docs").is_err());
+        assert_eq!(
+            Config::default().component("docs"),
+            Ok(Component::new("docs"))
+        );
+    }
+
+    #[test]
+    fn test_parse_replacements() {
+        let config = Config::parse(
+            r#"
+            [[replacements]]
+            file = "src/version.rs"
+            search_regex = 'VERSION: &str = "[^"]*"'
+            replace_template = 'VERSION: &str = "{version}"'
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.replacements,
+            Some(vec![Replacement {
+                file: "src/version.rs".to_owned(),
+                search_regex: "VERSION: &str = \"[^\"]*\"".to_owned(),
+                replace_template: "VERSION: &str = \"{version}\"".to_owned(),
+            }])
+        );
+        assert!(Config::parse(
+            "[[replacements]]\nfile = 'x'\nsearch_regex = '('\nreplace_template = ''"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_commit_parsers() {
+        let config = Config::parse(
+            r#"
+            changelog = "CHANGELOG.md"
+            commit_parsers = [{ message = "^feat", group = "Added" }, { body = "(?i)security", skip = true }]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.changelog.as_deref(), Some("CHANGELOG.md"));
+        assert_eq!(config.commit_parsers().len(), 2);
+        assert_eq!(
+            config.commit_parsers()[1].body.as_deref(),
+            Some("(?i)security")
+        );
+        assert!(config.commit_parsers()[1].skip);
+        assert_eq!(
+            Config::default().commit_parsers()[0].group.as_deref(),
+            Some("Features")
+        );
+        assert!(Config::parse("commit_parsers = [{ group = 'Other' }]").is_err());
+        assert!(Config::parse("commit_parsers = [{ message = '(' }]").is_err());
+    }
+
+    #[test]
+    fn test_parse_duplicate_component() {
+        assert!(
+            Config::parse("[[components]]\nname = 'api'\n[[components]]\nname = 'api'").is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_branch_pattern() {
+        assert!(Config::parse("release_branches = [\"release/[\"]").is_err());
+    }
+
+    #[test]
+    fn test_branch_policy_matches_glob() {
+        let policy = BranchPolicy::new("release/*");
+
+        assert!(policy.matches("release/1.x"));
+        assert!(!policy.matches("release/1.x/hotfix"));
+        assert!(!policy.matches("main"));
+        assert!(BranchPolicy::new("release/**").matches("release/1.x/hotfix"));
+    }
+
+    #[test]
+    fn test_branch_policy_bumps() {
+        let policy =
+            Config::parse("release_branches = [{ pattern = \"support/*\", bumps = [\"patch\"] }]")
+                .unwrap()
+                .release_branches(None)
+                .remove(0);
+        let version: Version = "1.2.4".parse().unwrap();
+
+        assert!(policy.check(Some(Type::Patch), &version).is_ok());
+        assert!(policy.check(Some(Type::Minor), &version).is_err());
+    }
+
+    #[test]
+    fn test_branch_policy_prerelease_only() {
+        let policy = BranchPolicy {
+            prerelease_only: true,
+            ..BranchPolicy::new("develop")
+        };
+
+        assert!(policy
+            .check(Some(Type::Minor), &"1.3.0-rc.1".parse().unwrap())
+            .is_ok());
+        assert!(policy
+            .check(Some(Type::Minor), &"1.3.0".parse().unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_project_overrides_user() {
+        let user = Config::parse("push = \"origin\"\nbump = \"patch\"").unwrap();
+        let project = Config::parse("bump = \"minor\"").unwrap();
+
+        let config = project.or(user);
+
+        assert_eq!(config.push.as_deref(), Some("origin"));
+        assert_eq!(config.bump, Some(Type::Minor));
+    }
+
+    #[test]
+    fn test_read_missing_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+
+        assert_eq!(
+            Config::read(&dir.path().join(PROJECT_CONFIG_FILE)),
+            Ok(None)
+        );
+    }
+}
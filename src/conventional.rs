@@ -0,0 +1,97 @@
+use regex::Regex;
+
+use crate::elements::Type;
+
+/// Matches the header of a conventional commit, e.g. `feat(parser)!: add new syntax`.
+const HEADER_REGEX: &str = r"^(\w+)(?:\(([^)]*)\))?(!)?: (.+)$";
+
+/// A commit message following the Conventional Commits specification, see
+/// <https://www.conventionalcommits.org>.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConventionalCommit {
+    pub kind: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub description: String,
+}
+
+impl ConventionalCommit {
+    /// Parse a full commit message. Returns None if the header does not follow the convention.
+    ///
+    /// # Example
+    /// ```
+    /// let commit = ConventionalCommit::parse("fix(tags)!: drop support for lightweight tags");
+    /// assert_eq!(commit.unwrap().bump(), Some(Type::Major));
+    /// ```
+    pub fn parse(message: &str) -> Option<Self> {
+        // Safety: Regex is verified to be valid
+        let re = Regex::new(HEADER_REGEX).unwrap();
+        let mut lines = message.lines();
+        let captures = re.captures(lines.next()?.trim_end())?;
+
+        let breaking_footer = lines.any(|line| {
+            line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:")
+        });
+
+        Some(ConventionalCommit {
+            kind: captures[1].to_lowercase(),
+            scope: captures.get(2).map(|m| m.as_str().to_owned()),
+            breaking: captures.get(3).is_some() || breaking_footer,
+            description: captures[4].to_owned(),
+        })
+    }
+
+    /// The version element this commit requires to be bumped, if any.
+    pub fn bump(&self) -> Option<Type> {
+        if self.breaking {
+            Some(Type::Major)
+        } else if self.kind == "feat" {
+            Some(Type::Minor)
+        } else if self.kind == "fix" {
+            Some(Type::Patch)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_with_scope() {
+        let commit = ConventionalCommit::parse("feat(cli): add --auto flag").unwrap();
+
+        assert_eq!(commit.kind, "feat");
+        assert_eq!(commit.scope.as_deref(), Some("cli"));
+        assert!(!commit.breaking);
+        assert_eq!(commit.description, "add --auto flag");
+    }
+
+    #[test]
+    fn test_parse_unconventional() {
+        assert_eq!(ConventionalCommit::parse("Update README"), None);
+        assert_eq!(ConventionalCommit::parse(""), None);
+    }
+
+    #[test]
+    fn test_bump_by_kind() {
+        let bump = |message| ConventionalCommit::parse(message).unwrap().bump();
+
+        assert_eq!(bump("feat: new prompt"), Some(Type::Minor));
+        assert_eq!(bump("fix: crash on empty repo"), Some(Type::Patch));
+        assert_eq!(bump("docs: typo"), None);
+    }
+
+    #[test]
+    fn test_bump_breaking() {
+        let bump = |message| ConventionalCommit::parse(message).unwrap().bump();
+
+        assert_eq!(bump("refactor!: rename flags"), Some(Type::Major));
+        assert_eq!(
+            bump("fix: parse tags\n\nBREAKING CHANGE: prefix is now mandatory"),
+            Some(Type::Major)
+        );
+    }
+}
@@ -1,9 +1,51 @@
+use std::cmp::Ordering;
 use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
 
+use regex::Regex;
+
+/// Matches a tag consisting of an arbitrary prefix followed by a SemVer 2.0 version, see
+/// <https://semver.org/#is-there-a-suggested-regular-expression-regex-to-check-a-semver-string>.
+/// The prefix is matched lazily, so `v10.2.3` yields the prefix `v` and not `v1`.
+const SEMVER_REGEX: &str = r"^(.*?)(0|[1-9]\d*)\.(0|[1-9]\d*)\.(0|[1-9]\d*)(?:-((?:0|[1-9]\d*|\d*[a-zA-Z-][0-9a-zA-Z-]*)(?:\.(?:0|[1-9]\d*|\d*[a-zA-Z-][0-9a-zA-Z-]*))*))?(?:\+([0-9a-zA-Z-]+(?:\.[0-9a-zA-Z-]+)*))?$";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Type {
     Major,
     Minor,
     Patch,
+    /// Increment the pre-release number, e.g. `1.3.0-rc.1` to `1.3.0-rc.2`
+    Prerelease,
+    /// Promote a pre-release to its release, e.g. `1.3.0-rc.2` to `1.3.0`
+    Release,
+}
+
+impl Type {
+    /// Rank of the element, a higher rank means a more significant change. Pre-release actions
+    /// rank below all version element bumps.
+    fn rank(&self) -> u8 {
+        match self {
+            Type::Major => 4,
+            Type::Minor => 3,
+            Type::Patch => 2,
+            Type::Release => 1,
+            Type::Prerelease => 0,
+        }
+    }
+}
+
+impl PartialOrd for Type {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Type {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
 }
 
 impl fmt::Display for Type {
@@ -12,6 +54,344 @@ impl fmt::Display for Type {
             Type::Major => write!(f, "Major"),
             Type::Minor => write!(f, "Minor"),
             Type::Patch => write!(f, "Patch"),
+            Type::Prerelease => write!(f, "Pre-release"),
+            Type::Release => write!(f, "Release"),
+        }
+    }
+}
+
+/// Strategy to select the tag of the last release.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TagSelection {
+    /// The tag nearest to HEAD by commit distance, as found by `git describe`
+    #[default]
+    Nearest,
+    /// The highest version among the tags reachable from HEAD
+    HighestReachable,
+    /// The highest version among all tags in the repository
+    Highest,
+}
+
+/// Selects the tags considered as releases when looking up the last version.
+#[derive(Debug, Clone)]
+pub struct TagFilter {
+    /// Glob pattern the tag names have to match
+    pub pattern: String,
+    /// Regex the tag names have to match
+    pub include: Option<Regex>,
+    /// Regex of tag names to skip
+    pub exclude: Option<Regex>,
+    /// Version prefixes of tags belonging to other version streams, e.g. `api/v`
+    pub excluded_prefixes: Vec<String>,
+}
+
+impl TagFilter {
+    /// Create a filter only matching tags by the given glob pattern.
+    pub fn new(pattern: &str) -> Self {
+        TagFilter {
+            pattern: pattern.to_owned(),
+            include: None,
+            exclude: None,
+            excluded_prefixes: Vec::new(),
+        }
+    }
+
+    /// Returns true if the tag name passes the include and exclude regexes. The glob pattern is
+    /// applied when listing tags.
+    pub fn matches(&self, tag_name: &str) -> bool {
+        self.include.as_ref().is_none_or(|re| re.is_match(tag_name))
+            && !self
+                .exclude
+                .as_ref()
+                .is_some_and(|re| re.is_match(tag_name))
+    }
+
+    /// Returns true if versions with the given prefix belong to the filtered version stream.
+    pub fn matches_prefix(&self, prefix: &str) -> bool {
+        !self
+            .excluded_prefixes
+            .iter()
+            .any(|excluded| excluded == prefix)
+    }
+}
+
+/// A single dot separated identifier of a pre-release version.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Identifier {
+    Numeric(u64),
+    AlphaNumeric(String),
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Identifier::Numeric(n) => write!(f, "{}", n),
+            Identifier::AlphaNumeric(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<&str> for Identifier {
+    fn from(s: &str) -> Self {
+        match s.parse::<u64>() {
+            Ok(n) if !s.starts_with('0') || s == "0" => Identifier::Numeric(n),
+            _ => Identifier::AlphaNumeric(s.to_owned()),
+        }
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Identifier {
+    /// Numeric identifiers are compared numerically, alphanumeric ones lexically in ASCII order.
+    /// Numeric identifiers always have lower precedence than alphanumeric ones.
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Identifier::Numeric(a), Identifier::Numeric(b)) => a.cmp(b),
+            (Identifier::Numeric(_), Identifier::AlphaNumeric(_)) => Ordering::Less,
+            (Identifier::AlphaNumeric(_), Identifier::Numeric(_)) => Ordering::Greater,
+            (Identifier::AlphaNumeric(a), Identifier::AlphaNumeric(b)) => a.cmp(b),
+        }
+    }
+}
+
+/// A semantic version according to SemVer 2.0, together with the prefix of the tag it was read
+/// from.
+///
+/// # Example
+/// ```
+/// let version: Version = "v1.2.3-alpha.1+build.5".parse().unwrap();
+/// assert_eq!(version.prefix, "v");
+/// assert_eq!(version.to_string(), "v1.2.3-alpha.1+build.5");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Version {
+    pub prefix: String,
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Vec<Identifier>,
+    pub build: Vec<String>,
+}
+
+impl Version {
+    /// Returns true if the version carries pre-release identifiers.
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty()
+    }
+
+    /// The pre-release channel, i.e. the first pre-release identifier if it is alphanumeric.
+    pub fn channel(&self) -> Option<&str> {
+        match self.pre.first() {
+            Some(Identifier::AlphaNumeric(channel)) => Some(channel),
+            _ => None,
         }
     }
+
+    /// Compare two versions by SemVer precedence, ignoring the tag prefix and build metadata.
+    pub fn cmp_precedence(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                // A pre-release version has lower precedence than the associated normal version
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}.{}.{}",
+            self.prefix, self.major, self.minor, self.patch
+        )?;
+        if !self.pre.is_empty() {
+            let pre: Vec<String> = self.pre.iter().map(|i| i.to_string()).collect();
+            write!(f, "-{}", pre.join("."))?;
+        }
+        if !self.build.is_empty() {
+            write!(f, "+{}", self.build.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Compiled once, repositories with many tags parse a version for each of them
+        static RE: OnceLock<Regex> = OnceLock::new();
+        // Safety: Regex is verified to be valid
+        let re = RE.get_or_init(|| Regex::new(SEMVER_REGEX).unwrap());
+        let captures = re
+            .captures(s)
+            .ok_or_else(|| format!("No semantic version found in: {}", s))?;
+
+        // Safety: Regex is defined to match numbers, only overflowing values can fail to parse
+        let number = |i: usize| {
+            captures[i]
+                .parse::<u64>()
+                .map_err(|e| format!("Invalid version number in {}: {}", s, e))
+        };
+
+        Ok(Version {
+            prefix: captures[1].to_owned(),
+            major: number(2)?,
+            minor: number(3)?,
+            patch: number(4)?,
+            pre: captures
+                .get(5)
+                .map(|m| m.as_str().split('.').map(Identifier::from).collect())
+                .unwrap_or_default(),
+            build: captures
+                .get(6)
+                .map(|m| m.as_str().split('.').map(str::to_owned).collect())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    /// Orders by SemVer precedence. Versions of equal precedence are ordered by prefix and build
+    /// metadata to stay consistent with `Eq`.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_precedence(other)
+            .then_with(|| self.prefix.cmp(&other.prefix))
+            .then_with(|| self.build.cmp(&other.build))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_order() {
+        assert!(Type::Major > Type::Minor);
+        assert!(Type::Minor > Type::Patch);
+        assert!(Type::Patch > Type::Release);
+        assert_eq!(
+            [Type::Patch, Type::Major, Type::Minor].iter().max(),
+            Some(&Type::Major)
+        );
+    }
+
+    #[test]
+    fn test_tag_filter() {
+        let filter = TagFilter {
+            include: Some(Regex::new(r"^v\d").unwrap()),
+            exclude: Some(Regex::new(r"-beta").unwrap()),
+            ..TagFilter::new("*")
+        };
+
+        assert!(filter.matches("v1.2.3"));
+        assert!(!filter.matches("v1.2.3-beta.1"));
+        assert!(!filter.matches("build-2023.10.01"));
+        assert!(TagFilter::new("*").matches("build-2023.10.01"));
+    }
+
+    #[test]
+    fn test_version_parse_valid() {
+        let version: Version = "v1.2.3-alpha".parse().unwrap();
+
+        assert_eq!(version.prefix, "v");
+        assert_eq!((version.major, version.minor, version.patch), (1, 2, 3));
+        assert_eq!(version.pre, vec![Identifier::from("alpha")]);
+        assert!(version.build.is_empty());
+    }
+
+    #[test]
+    fn test_version_parse_invalid() {
+        assert!("invalid-tag".parse::<Version>().is_err());
+        assert!("v1.02.3".parse::<Version>().is_err());
+    }
+
+    #[test]
+    fn test_version_parse_no_prefix() {
+        let version: Version = "1.2.3-beta".parse().unwrap();
+
+        assert_eq!(version.prefix, "");
+        assert_eq!(version.pre, vec![Identifier::from("beta")]);
+    }
+
+    #[test]
+    fn test_version_parse_multi_digit() {
+        let version: Version = "v10.20.30".parse().unwrap();
+
+        assert_eq!(version.prefix, "v");
+        assert_eq!((version.major, version.minor, version.patch), (10, 20, 30));
+    }
+
+    #[test]
+    fn test_version_parse_build_metadata() {
+        let version: Version = "release-1.0.0-rc.1+exp.sha.5114f85".parse().unwrap();
+
+        assert_eq!(version.prefix, "release-");
+        assert_eq!(
+            version.pre,
+            vec![
+                Identifier::AlphaNumeric("rc".to_owned()),
+                Identifier::Numeric(1)
+            ]
+        );
+        assert_eq!(version.build, vec!["exp", "sha", "5114f85"]);
+    }
+
+    #[test]
+    fn test_version_display_roundtrip() {
+        for tag in [
+            "v1.2.3",
+            "1.0.0-alpha.1",
+            "api/v2.0.0+build.7",
+            "1.0.0-0.3.7",
+        ] {
+            assert_eq!(tag.parse::<Version>().unwrap().to_string(), tag);
+        }
+    }
+
+    #[test]
+    fn test_version_precedence() {
+        let ordered = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "1.0.1",
+            "1.1.0",
+            "2.0.0",
+        ];
+        let versions: Vec<Version> = ordered.iter().map(|v| v.parse().unwrap()).collect();
+
+        for pair in versions.windows(2) {
+            assert!(pair[0] < pair[1], "{} < {}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn test_version_precedence_ignores_build() {
+        let a: Version = "1.0.0+build.1".parse().unwrap();
+        let b: Version = "v1.0.0+build.2".parse().unwrap();
+
+        assert_eq!(a.cmp_precedence(&b), Ordering::Equal);
+    }
 }
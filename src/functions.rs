@@ -1,90 +1,437 @@
-use git2::{ObjectType, Repository, Signature};
-use inquire::{Confirm, Select};
+use git2::{
+    BranchType, Commit, Cred, CredentialType, DiffOptions, Index, IndexEntry, IndexTime,
+    ObjectType, Oid, PushOptions, RemoteCallbacks, Repository, Signature, Tree,
+};
+use inquire::validator::Validation;
+use inquire::{Confirm, InquireError, Select, Text};
 use log::{debug, info, LevelFilter};
-
 use regex::Regex;
 use simple_logger::SimpleLogger;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::IsTerminal;
+use std::path::Path;
 
-use crate::elements::Type;
+use crate::changelog::CommitRules;
+use crate::config::BranchPolicy;
+use crate::conventional::ConventionalCommit;
+use crate::elements::{Identifier, TagFilter, TagSelection, Type, Version};
+use crate::manifest::FileUpdate;
+use crate::message::DEFAULT_MESSAGE_TEMPLATE;
+use crate::signing::{format_signature, sign_buffer, sign_tags_by_default};
 
-const SEMVER_REGEX: &str = r"(.*)(\d+)\.(\d+)\.(\d+)(.*)";
+/// Valid pre-release channel names, alphanumeric identifiers that are not purely numeric.
+const CHANNEL_REGEX: &str = r"^[0-9A-Za-z-]*[A-Za-z-][0-9A-Za-z-]*$";
 
-/// Find the latest tag containing a semantic version in the given repository that is reachable
-/// from the currently checked out commit.
+/// Find the latest tag containing a semantic version in the given repository according to the
+/// selection strategy. Multiple tags on the same commit are resolved by version precedence.
+/// Returns None if there is no such tag.
 ///
 /// * `repo`: Repository to look for tag
-pub fn find_latest_semver_tag(repo: &Repository) -> Result<String, git2::Error> {
-    // Create a DescribeOptions struct
-    let mut opts = git2::DescribeOptions::new();
-    let mut format_opts = git2::DescribeFormatOptions::new();
-    opts.describe_tags(); // Use tags as references
-    opts.pattern("*[0-9]*.[0-9]*.[0-9]*");
-    format_opts.abbreviated_size(0);
-    opts.show_commit_oid_as_fallback(false); // Do not show commit id if no tag is found
+/// * `filter`: Filter the tag has to pass
+/// * `selection`: Strategy to select the tag
+pub fn find_latest_semver_tag(
+    repo: &Repository,
+    filter: &TagFilter,
+    selection: TagSelection,
+) -> Result<Option<String>, git2::Error> {
+    let tags = semver_tags(repo, filter)?;
+    let latest = match selection {
+        TagSelection::Highest => tags
+            .into_iter()
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, tag_name, _)| tag_name),
+        TagSelection::HighestReachable => reachable_tags(repo, tags)?
+            .into_iter()
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, tag_name, _)| tag_name),
+        TagSelection::Nearest => nearest_tag(repo, reachable_tags(repo, tags)?)?,
+    };
 
-    // Get the most recent tag name
-    let tag_name = repo.describe(&opts)?.format(Some(&format_opts))?;
+    match &latest {
+        Some(tag_name) => debug!("The most recent tag is: {}", tag_name),
+        None => debug!("No semantic version tag found"),
+    }
+    Ok(latest)
+}
 
-    debug!("The most recent tag is: {}", tag_name);
-    Ok(tag_name)
+/// The given tags whose commit is reachable from HEAD, found in a single walk of the history.
+fn reachable_tags(
+    repo: &Repository,
+    tags: Vec<(Version, String, Oid)>,
+) -> Result<Vec<(Version, String, Oid)>, git2::Error> {
+    let mut pending: HashSet<Oid> = tags.iter().map(|(_, _, commit)| *commit).collect();
+    let mut reachable = HashSet::new();
+    if !pending.is_empty() {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+        for oid in revwalk {
+            let oid = oid?;
+            if pending.remove(&oid) {
+                reachable.insert(oid);
+                if pending.is_empty() {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(tags
+        .into_iter()
+        .filter(|(_, _, commit)| reachable.contains(commit))
+        .collect())
 }
 
-/// Returns true if provided repository has master/main branch checked out, false otherwise.
+/// The tag with the fewest commits between it and HEAD, like `git describe`, preferring the highest
+/// version among tags at the same distance.
 ///
-/// * `repo`: Repository to check
-pub fn on_master_branch(repo: &Repository) -> bool {
-    if let Ok(head) = repo.head() {
-        // Get the shorthand reference name (e.g., "refs/heads/master")
-        if let Some(branch_name) = head.shorthand() {
-            // Compare the branch name to "master"
-            if branch_name == "master" || branch_name == "main" {
-                return true;
+/// * `repo`: Repository of the tags
+/// * `tags`: Tags reachable from HEAD
+fn nearest_tag(
+    repo: &Repository,
+    tags: Vec<(Version, String, Oid)>,
+) -> Result<Option<String>, git2::Error> {
+    // A tagged commit that is an ancestor of another tagged commit is always farther away from
+    // HEAD, so a single walk over the ancestors of all tagged commits rules it out
+    let tagged: HashSet<Oid> = tags.iter().map(|(_, _, commit)| *commit).collect();
+    let mut revwalk = repo.revwalk()?;
+    for oid in &tagged {
+        for parent in repo.find_commit(*oid)?.parent_ids() {
+            revwalk.push(parent)?;
+        }
+    }
+    let mut covered = HashSet::new();
+    for oid in revwalk {
+        let oid = oid?;
+        if tagged.contains(&oid) {
+            covered.insert(oid);
+        }
+    }
+
+    let head = repo.head()?.peel_to_commit()?.id();
+    let mut candidates = Vec::new();
+    for (version, tag_name, commit) in tags {
+        if covered.contains(&commit) {
+            continue;
+        }
+        // Number of commits reachable from HEAD but not from the tag
+        let distance = match commit == head {
+            true => 0,
+            false => repo.graph_ahead_behind(head, commit)?.0,
+        };
+        candidates.push((distance, version, tag_name));
+    }
+    Ok(candidates
+        .into_iter()
+        // Prefer the lowest distance, then the highest version
+        .max_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)))
+        .map(|(_, _, tag_name)| tag_name))
+}
+
+/// List all tags passing the filter that contain a semantic version, together with their version
+/// and the commit they point to.
+///
+/// * `repo`: Repository to look for tags
+/// * `filter`: Filter the tags have to pass
+pub fn semver_tags(
+    repo: &Repository,
+    filter: &TagFilter,
+) -> Result<Vec<(Version, String, Oid)>, git2::Error> {
+    let mut tags = Vec::new();
+    for tag_name in repo.tag_names(Some(&filter.pattern))?.iter().flatten() {
+        if !filter.matches(tag_name) {
+            debug!("Ignoring tag {}", tag_name);
+            continue;
+        }
+        let Ok(version) = tag_name.parse::<Version>() else {
+            debug!("Ignoring tag {} without semantic version", tag_name);
+            continue;
+        };
+        if !filter.matches_prefix(&version.prefix) {
+            debug!("Ignoring tag {} of another component", tag_name);
+            continue;
+        }
+        let commit = repo
+            .revparse_single(&format!("refs/tags/{}", tag_name))?
+            .peel_to_commit()?;
+        tags.push((version, tag_name.to_owned(), commit.id()));
+    }
+    Ok(tags)
+}
+
+/// Collect all commits reachable from HEAD but not from the given tag, newest first. Without a tag
+/// all commits reachable from HEAD are returned.
+///
+/// * `repo`: Repository to walk
+/// * `tag_name`: Name of the tag marking the last release
+pub fn commits_since<'r>(
+    repo: &'r Repository,
+    tag_name: Option<&str>,
+) -> Result<Vec<Commit<'r>>, git2::Error> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    if let Some(tag_name) = tag_name {
+        let tag_commit = repo
+            .revparse_single(&format!("refs/tags/{}", tag_name))?
+            .peel_to_commit()?;
+        revwalk.hide(tag_commit.id())?;
+    }
+
+    revwalk
+        .map(|oid| repo.find_commit(oid?))
+        .collect::<Result<Vec<_>, _>>()
+}
+
+/// Keep only the commits changing files matched by the given pathspecs. Merge commits are skipped
+/// as their changes are attributed to the merged commits. All commits are kept if no paths are
+/// given.
+///
+/// * `repo`: Repository of the commits
+/// * `commits`: Commits to filter
+/// * `paths`: Git pathspecs, e.g. `services/api/` or `*.proto`
+pub fn commits_touching<'r>(
+    repo: &'r Repository,
+    commits: Vec<Commit<'r>>,
+    paths: &[String],
+) -> Result<Vec<Commit<'r>>, git2::Error> {
+    if paths.is_empty() {
+        return Ok(commits);
+    }
+
+    let mut touching = Vec::new();
+    for commit in commits {
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        if diff_touches(repo, parent_tree.as_ref(), &commit.tree()?, paths)? {
+            touching.push(commit);
+        }
+    }
+    Ok(touching)
+}
+
+/// Drop the commits contained in a tag matching the given regex, e.g. the commits of a skipped
+/// pre-release.
+///
+/// * `repo`: Repository of the commits
+/// * `commits`: Commits to filter
+/// * `skip_tags`: Regex of the skipped tags
+pub fn commits_outside_tags<'r>(
+    repo: &'r Repository,
+    commits: Vec<Commit<'r>>,
+    skip_tags: &Regex,
+) -> Result<Vec<Commit<'r>>, git2::Error> {
+    let mut skipped = Vec::new();
+    for tag_name in repo.tag_names(None)?.iter().flatten() {
+        if skip_tags.is_match(tag_name) {
+            debug!("Leaving out the commits of skipped tag {}", tag_name);
+            let target = repo.revparse_single(&format!("refs/tags/{}", tag_name))?;
+            skipped.push(target.peel_to_commit()?.id());
+        }
+    }
+
+    let mut outside = Vec::new();
+    for commit in commits {
+        let mut contained = false;
+        for tag_commit in &skipped {
+            contained |=
+                *tag_commit == commit.id() || repo.graph_descendant_of(*tag_commit, commit.id())?;
+        }
+        if !contained {
+            outside.push(commit);
+        }
+    }
+    Ok(outside)
+}
+
+/// Returns true if files matched by the given pathspecs differ between the tag and HEAD. Without
+/// tag, any matching file in HEAD counts as change.
+///
+/// * `repo`: Repository to compare in
+/// * `tag_name`: Name of the tag to compare HEAD to
+/// * `paths`: Git pathspecs, the whole tree if empty
+pub fn paths_changed_since(
+    repo: &Repository,
+    tag_name: Option<&str>,
+    paths: &[String],
+) -> Result<bool, git2::Error> {
+    let head = repo.head()?.peel_to_tree()?;
+    let old = match tag_name {
+        Some(tag_name) => Some(
+            repo.revparse_single(&format!("refs/tags/{}", tag_name))?
+                .peel_to_tree()?,
+        ),
+        None => None,
+    };
+    diff_touches(repo, old.as_ref(), &head, paths)
+}
+
+/// Returns true if the diff between the given trees contains files matched by the pathspecs.
+fn diff_touches(
+    repo: &Repository,
+    old: Option<&Tree>,
+    new: &Tree,
+    paths: &[String],
+) -> Result<bool, git2::Error> {
+    let mut opts = DiffOptions::new();
+    for path in paths {
+        opts.pathspec(path);
+    }
+    let diff = repo.diff_tree_to_tree(old, Some(new), Some(&mut opts))?;
+    Ok(diff.deltas().len() > 0)
+}
+
+/// Infer the version element to bump from the Conventional Commits messages of the given commits.
+/// Returns the most significant bump together with the commits that require it, or None if no
+/// commit requires a bump.
+///
+/// * `commits`: Commits since the last release
+/// * `rules`: Rules preprocessing the messages, commits skipped by a parser require no bump
+pub fn infer_bump<'c, 'r>(
+    commits: &'c [Commit<'r>],
+    rules: &CommitRules,
+) -> Option<(Type, Vec<&'c Commit<'r>>)> {
+    let classified: Vec<(Type, &Commit)> = commits
+        .iter()
+        .filter_map(|commit| {
+            let message = rules.preprocess(commit.message().unwrap_or_default());
+            if rules.skips(&message) {
+                debug!("{} is skipped by a commit parser", commit.id());
+                return None;
             }
+            let bump = ConventionalCommit::parse(&message)?.bump()?;
+            debug!("{} requires a {} bump", commit.id(), bump);
+            Some((bump, commit))
+        })
+        .collect();
+
+    let bump = classified.iter().map(|(bump, _)| *bump).max()?;
+    let reasons = classified
+        .into_iter()
+        .filter(|(b, _)| *b == bump)
+        .map(|(_, commit)| commit)
+        .collect();
+
+    Some((bump, reasons))
+}
+
+/// Determine the default branch of the repository from the HEAD of its remotes, preferring
+/// `origin`, falling back to `init.defaultBranch` if such a local or remote-tracking branch exists.
+/// Returns None if neither is available.
+///
+/// * `repo`: Repository to inspect
+pub fn default_branch(repo: &Repository) -> Option<String> {
+    let mut remotes: Vec<String> = repo
+        .remotes()
+        .map(|remotes| remotes.iter().flatten().map(str::to_owned).collect())
+        .unwrap_or_default();
+    remotes.sort_by_key(|remote| remote != "origin");
+
+    for remote in &remotes {
+        let prefix = format!("refs/remotes/{}/", remote);
+        let branch = repo
+            .find_reference(&format!("{}HEAD", prefix))
+            .ok()
+            .and_then(|head| head.symbolic_target().map(str::to_owned))
+            .and_then(|target| target.strip_prefix(&prefix).map(str::to_owned));
+        if let Some(branch) = branch {
+            debug!("Default branch from {} HEAD: {}", remote, branch);
+            return Some(branch);
         }
     }
-    false
-}
-
-/// Extract the semantic version parts major, minor and patch from a string as well as their pre-
-/// and suffixes.
-/// It is assumed the string contains exactly one section with three consecutive numbers, separated
-/// by a period (.).
-///
-/// # Example
-/// ```
-/// let tag = "v1.2.3-alpha";
-/// let result = split_tag_semver(tag);
-/// assert_eq!(result, Some(("v".to_owned(), 1, 2, 3, "-alpha".to_owned())));
-/// ```
-pub fn split_tag_semver(tag: &str) -> Option<(String, u32, u32, u32, String)> {
-    // Safety: Regex is verified to be valid
-    let re = Regex::new(SEMVER_REGEX).unwrap();
-
-    if let Some(captures) = re.captures(tag) {
-        let tag_prefix = captures.get(1).unwrap().as_str();
-        let major = captures.get(2).unwrap().as_str();
-        let minor = captures.get(3).unwrap().as_str();
-        let patch = captures.get(4).unwrap().as_str();
-        let tag_suffix = captures.get(5).unwrap().as_str();
-
-        debug!("Matched the following tag parts:");
-        debug!("Prefix: {}", tag_prefix);
-        debug!("Major: {}", major);
-        debug!("Minor: {}", minor);
-        debug!("Patch: {}", patch);
-        debug!("Suffix: {}", tag_suffix);
-
-        Some((
-            tag_prefix.to_owned(),
-            // Safety: Regex is defined to match numbers, so parsing to numbers must always succeed
-            major.parse::<u32>().unwrap(),
-            minor.parse::<u32>().unwrap(),
-            patch.parse::<u32>().unwrap(),
-            tag_suffix.to_owned(),
-        ))
-    } else {
-        None
+
+    let branch = repo.config().ok()?.get_string("init.defaultBranch").ok()?;
+    // A fresh clone may only have the remote-tracking branch
+    let exists = repo.find_branch(&branch, BranchType::Local).is_ok()
+        || remotes.iter().any(|remote| {
+            repo.find_reference(&format!("refs/remotes/{}/{}", remote, branch))
+                .is_ok()
+        });
+    if exists {
+        debug!("Default branch from init.defaultBranch: {}", branch);
+        return Some(branch);
     }
+    None
+}
+
+/// Returns the policy of the release branch checked out in the provided repository, or None if
+/// the checked out branch is not a release branch. A detached HEAD is accepted if it is contained
+/// in a local or remote-tracking release branch, as is common for CI checkouts.
+///
+/// * `repo`: Repository to check
+/// * `release_branches`: Policies of the branches releases may be tagged on
+pub fn release_branch_policy<'p>(
+    repo: &Repository,
+    release_branches: &'p [BranchPolicy],
+) -> Option<&'p BranchPolicy> {
+    let head = repo.head().ok()?;
+    if repo.head_detached().unwrap_or(false) {
+        return detached_head_policy(repo, head.target()?, release_branches);
+    }
+
+    // Get the shorthand reference name (e.g., "refs/heads/master")
+    let branch_name = head.shorthand()?;
+    let policy = release_branches
+        .iter()
+        .find(|policy| policy.matches(branch_name))?;
+
+    debug!(
+        "Branch {} matches release branch {}",
+        branch_name, policy.pattern
+    );
+    Some(policy)
+}
+
+/// Returns the policy of the first release branch containing the detached HEAD commit. Remote
+/// tracking branches are matched without their remote name, so `origin/main` matches `main`.
+///
+/// * `repo`: Repository to check
+/// * `head`: Commit the detached HEAD points to
+/// * `release_branches`: Policies of the branches releases may be tagged on
+fn detached_head_policy<'p>(
+    repo: &Repository,
+    head: Oid,
+    release_branches: &'p [BranchPolicy],
+) -> Option<&'p BranchPolicy> {
+    for (branch, _) in repo.branches(None).ok()?.flatten() {
+        let reference = branch.get();
+        let (Some(refname), Some(tip)) = (reference.name(), reference.target()) else {
+            // Symbolic references like origin/HEAD point to another branch anyway
+            continue;
+        };
+        let Some(shorthand) = reference.shorthand() else {
+            continue;
+        };
+        let name = match repo.branch_remote_name(refname) {
+            Ok(remote) => shorthand
+                .strip_prefix(remote.as_str().unwrap_or_default())
+                .and_then(|name| name.strip_prefix('/'))
+                .unwrap_or(shorthand),
+            Err(_) => shorthand,
+        };
+
+        let Some(policy) = release_branches.iter().find(|policy| policy.matches(name)) else {
+            continue;
+        };
+        if tip == head || repo.graph_descendant_of(tip, head).unwrap_or(false) {
+            info!(
+                "Detached HEAD {} is contained in release branch {}",
+                head, shorthand
+            );
+            return Some(policy);
+        }
+    }
+
+    debug!(
+        "Detached HEAD {} is not contained in any release branch",
+        head
+    );
+    None
 }
 
 /// Set logging to the desired level.
@@ -99,153 +446,1183 @@ pub fn initialize_logging(debug: u8) {
     }
 }
 
-/// Create a new tag if confirmed by a prompt on the HEAD of the provided repository.
+/// Returns true if stdin is attached to a terminal, i.e. the user can answer prompts.
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal()
+}
+
+/// Returns true if the user has to confirm the tag creation, which `--yes` skips. Fails if the
+/// confirmation is needed but the user cannot answer prompts.
+///
+/// * `yes`: Whether the creation is confirmed on the command line
+/// * `interactive`: Whether the user can answer prompts
+pub fn requires_confirmation(yes: bool, interactive: bool) -> Result<bool, String> {
+    match (yes, interactive) {
+        (true, _) => Ok(false),
+        (false, true) => Ok(true),
+        (false, false) => {
+            Err("Not running in a terminal, confirm the tag creation with --yes.".to_owned())
+        }
+    }
+}
+
+/// Ask the user to confirm the creation of the given tag.
 ///
-/// * `repo`: Repository to tag
 /// * `tag_name`: Name of the tag to create
-pub fn create_new_tag(repo: &Repository, tag_name: &str) -> Result<bool, git2::Error> {
-    // Confirm tag creation
-    let ans = Confirm::new(&format!("Create new tag {}?", tag_name))
+pub fn confirm_tag_creation(tag_name: &str) -> Result<bool, InquireError> {
+    Confirm::new(&format!("Create new tag {}?", tag_name))
         .with_default(true)
         .prompt()
-        .unwrap();
+}
 
-    if !ans {
-        info!("Aborting.");
-        return Ok(false);
+/// An annotated tag that is about to be created.
+pub struct NewTag<'r> {
+    pub name: String,
+    pub target: Commit<'r>,
+    pub tagger: Signature<'static>,
+    pub message: String,
+    pub sign: bool,
+}
+
+impl fmt::Display for NewTag<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Tag:     {}", self.name)?;
+        writeln!(
+            f,
+            "Target:  {} {}",
+            self.target.id(),
+            self.target.summary().unwrap_or_default()
+        )?;
+        writeln!(f, "Tagger:  {}", self.tagger)?;
+        writeln!(f, "Signed:  {}", if self.sign { "yes" } else { "no" })?;
+        write!(f, "Message: {}", self.message)
     }
+}
 
-    let tag_message = "Tag created by taggr";
+/// Prepare an annotated tag on the HEAD of the provided repository, tagged by the user configured
+/// in git. The tag is signed if `tag.gpgSign` is enabled.
+///
+/// * `repo`: Repository to tag
+/// * `tag_name`: Name of the tag to create
+pub fn prepare_new_tag<'r>(
+    repo: &'r Repository,
+    tag_name: &str,
+) -> Result<NewTag<'r>, git2::Error> {
     // Get the HEAD reference
     let head = repo.head()?;
-    let head_commit = head.peel(ObjectType::Commit)?;
+    let head_commit = head.peel_to_commit()?;
 
     // Read user information from Git configuration
     let config = repo.config()?;
     let user_name = config.get_string("user.name")?;
     let user_email = config.get_string("user.email")?;
 
-    // Create the annotated tag
-    let user_signature = Signature::now(&user_name, &user_email)?;
-    let tag_oid = repo.tag(tag_name, &head_commit, &user_signature, tag_message, false)?;
+    Ok(NewTag {
+        name: tag_name.to_owned(),
+        target: head_commit,
+        tagger: Signature::now(&user_name, &user_email)?,
+        message: DEFAULT_MESSAGE_TEMPLATE.to_owned(),
+        sign: sign_tags_by_default(&config),
+    })
+}
+
+/// Create a prepared annotated tag in the provided repository.
+///
+/// * `repo`: Repository to tag
+/// * `tag`: Tag to create
+pub fn create_new_tag(repo: &Repository, tag: &NewTag) -> Result<(), git2::Error> {
+    let tag_oid = if tag.sign {
+        create_signed_tag(repo, tag)?
+    } else {
+        repo.tag(
+            &tag.name,
+            tag.target.as_object(),
+            &tag.tagger,
+            &tag.message,
+            false,
+        )?
+    };
+
+    info!("Annotated tag created: {} on {}", tag.name, tag_oid);
+
+    Ok(())
+}
+
+/// Write a tag object with an embedded signature and point a new tag reference at it. Returns the
+/// id of the tag object.
+///
+/// * `repo`: Repository to tag
+/// * `tag`: Tag to create
+fn create_signed_tag(repo: &Repository, tag: &NewTag) -> Result<Oid, git2::Error> {
+    let mut buffer = format!(
+        "object {}\ntype commit\ntag {}\ntagger {}\n\n{}",
+        tag.target.id(),
+        tag.name,
+        format_signature(&tag.tagger),
+        tag.message
+    );
+    if !buffer.ends_with('\n') {
+        buffer.push('\n');
+    }
+
+    let config = repo.config()?;
+    let signature =
+        sign_buffer(&config, &buffer, &tag.tagger).map_err(|e| git2::Error::from_str(&e))?;
+    buffer.push_str(&signature);
+
+    let tag_oid = repo.odb()?.write(ObjectType::Tag, buffer.as_bytes())?;
+    repo.reference(
+        &format!("refs/tags/{}", tag.name),
+        tag_oid,
+        false,
+        "taggr: signed tag",
+    )?;
+    Ok(tag_oid)
+}
+
+/// Returns true if the given path is tracked in the tree of HEAD.
+///
+/// * `repo`: Repository to check
+/// * `path`: Path relative to the repository root
+pub fn is_tracked(repo: &Repository, path: &str) -> bool {
+    repo.head()
+        .and_then(|head| head.peel_to_tree())
+        .and_then(|tree| tree.get_path(Path::new(path)))
+        .is_ok()
+}
 
-    info!("Annotated tag created: {} on {}", tag_name, tag_oid);
+/// Commit the given file updates on top of HEAD without moving HEAD, so the release can be tagged
+/// before it is checked out with `checkout_release_commit`. The tree is built from HEAD, other
+/// staged changes are not included. Files not tracked in HEAD are added as regular files, unless
+/// they are ignored by git. The commit is signed if `commit.gpgSign` is enabled.
+///
+/// * `repo`: Repository to commit to
+/// * `updates`: Files to change, relative to the repository root
+/// * `message`: Message of the release commit
+pub fn create_release_commit<'r>(
+    repo: &'r Repository,
+    updates: &[FileUpdate],
+    message: &str,
+) -> Result<Commit<'r>, git2::Error> {
+    let head = repo.head()?.peel_to_commit()?;
+    let mut index = Index::new()?;
+    index.read_tree(&head.tree()?)?;
+    for update in updates {
+        let mut entry = match index.get_path(Path::new(&update.path), 0) {
+            Some(entry) => entry,
+            None if repo.is_path_ignored(&update.path)? => {
+                return Err(git2::Error::from_str(&format!(
+                    "{} is ignored by git and not added to the release commit",
+                    update.path
+                )));
+            }
+            None => new_index_entry(&update.path),
+        };
+        entry.id = repo.blob(update.new.as_bytes())?;
+        entry.file_size = update.new.len() as u32;
+        index.add(&entry)?;
+    }
+    let tree = repo.find_tree(index.write_tree_to(repo)?)?;
 
-    Ok(true)
+    let signature = repo.signature()?;
+    let config = repo.config()?;
+    let oid = if config.get_bool("commit.gpgSign").unwrap_or(false) {
+        let buffer = repo.commit_create_buffer(&signature, &signature, message, &tree, &[&head])?;
+        let buffer =
+            std::str::from_utf8(&buffer).map_err(|e| git2::Error::from_str(&e.to_string()))?;
+        let commit_signature =
+            sign_buffer(&config, buffer, &signature).map_err(|e| git2::Error::from_str(&e))?;
+        repo.commit_signed(buffer, &commit_signature, None)?
+    } else {
+        repo.commit(None, &signature, &signature, message, &tree, &[&head])?
+    };
+    info!("Release commit created: {}", oid);
+    repo.find_commit(oid)
+}
+
+/// Advance HEAD to a release commit created by `create_release_commit` and write the updated
+/// files to the working tree and index.
+///
+/// * `repo`: Repository of the release commit
+/// * `commit`: Release commit, a child of HEAD
+/// * `updates`: Files changed by the release commit
+pub fn checkout_release_commit(
+    repo: &Repository,
+    commit: &Commit,
+    updates: &[FileUpdate],
+) -> Result<(), git2::Error> {
+    repo.head()?
+        .set_target(commit.id(), "taggr: release commit")?;
+
+    let work_dir = repo
+        .workdir()
+        .ok_or_else(|| git2::Error::from_str("Cannot update files in a bare repository"))?;
+    let mut index = repo.index()?;
+    for update in updates {
+        let path = work_dir.join(&update.path);
+        path.parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, &update.new))
+            .map_err(|e| {
+                git2::Error::from_str(&format!("Could not write {}: {}", update.path, e))
+            })?;
+        index.add_path(Path::new(&update.path))?;
+    }
+    index.write()
+}
+
+/// Index entry of a regular file that is not tracked yet, its content is set by the caller.
+fn new_index_entry(path: &str) -> IndexEntry {
+    let time = IndexTime::new(0, 0);
+    IndexEntry {
+        ctime: time,
+        mtime: time,
+        dev: 0,
+        ino: 0,
+        mode: 0o100644,
+        uid: 0,
+        gid: 0,
+        file_size: 0,
+        id: Oid::zero(),
+        flags: 0,
+        flags_extended: 0,
+        path: path.as_bytes().to_vec(),
+    }
+}
+
+/// Push a tag to the given remote, authenticating with the credential helpers and SSH agent
+/// configured in git. The local tag is kept if the push fails.
+///
+/// * `repo`: Repository containing the tag
+/// * `remote_name`: Name of the remote to push to, e.g. `origin`
+/// * `tag_name`: Name of the tag to push
+/// * `branch`: Branch to push along with the tag, e.g. after a release commit
+pub fn push_tag(
+    repo: &Repository,
+    remote_name: &str,
+    tag_name: &str,
+    branch: Option<&str>,
+) -> Result<(), git2::Error> {
+    let mut remote = repo.find_remote(remote_name)?;
+    let config = repo.config()?;
+    let mut refspecs = vec![format!("refs/tags/{0}:refs/tags/{0}", tag_name)];
+    if let Some(branch) = branch {
+        refspecs.push(format!("refs/heads/{0}:refs/heads/{0}", branch));
+    }
+    let rejection: RefCell<Option<String>> = RefCell::new(None);
+
+    let mut callbacks = RemoteCallbacks::new();
+    let mut attempts = 0;
+    callbacks.credentials(|url, username, allowed| {
+        // libgit2 keeps asking as long as authentication fails, so give up eventually
+        attempts += 1;
+        if attempts > 3 {
+            return Err(git2::Error::from_str("Authentication failed"));
+        }
+        debug!("Requesting {:?} credentials for {}", allowed, url);
+        if allowed.contains(CredentialType::SSH_KEY) {
+            Cred::ssh_key_from_agent(username.unwrap_or("git"))
+        } else if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            Cred::credential_helper(&config, url, username)
+        } else if allowed.contains(CredentialType::USERNAME) {
+            Cred::username(username.unwrap_or("git"))
+        } else {
+            Cred::default()
+        }
+    });
+    callbacks.push_update_reference(|refname, status| {
+        if let Some(message) = status {
+            *rejection.borrow_mut() = Some(format!("{} rejected: {}", refname, message));
+        }
+        Ok(())
+    });
+
+    let mut push_options = PushOptions::new();
+    push_options.remote_callbacks(callbacks);
+    remote.push(&refspecs, Some(&mut push_options))?;
+
+    if let Some(message) = rejection.take() {
+        return Err(git2::Error::from_str(&message));
+    }
+
+    match branch {
+        Some(branch) => info!(
+            "Pushed tag {} and branch {} to {}",
+            tag_name, branch, remote_name
+        ),
+        None => info!("Pushed tag {} to {}", tag_name, remote_name),
+    }
+    Ok(())
 }
 
 /// Prompt the user which semantic version element shall be increased (Major, Minor, Patch).
+/// Pre-release actions are offered in addition if the current version is a pre-release.
 /// Returns the element.
-pub fn prompt_bump_element() -> Type {
-    let options: Vec<Type> = vec![Type::Major, Type::Minor, Type::Patch];
+///
+/// * `preselect`: Element the cursor initially points to
+/// * `prerelease`: Whether the current version is a pre-release
+pub fn prompt_bump_element(
+    preselect: Option<Type>,
+    prerelease: bool,
+) -> Result<Type, InquireError> {
+    let mut options: Vec<Type> = vec![Type::Major, Type::Minor, Type::Patch];
+    if prerelease {
+        options.extend([Type::Prerelease, Type::Release]);
+    }
+    let cursor = preselect
+        .and_then(|bump| options.iter().position(|option| *option == bump))
+        .unwrap_or(0);
 
     Select::new("Which version to bump?", options)
+        .with_starting_cursor(cursor)
         .prompt()
-        .unwrap()
 }
 
-/// Bump the version segments according to the selected bump.
+/// The first version of a repository without semantic version tags given with `--initial`. Returns
+/// None if the user has to be prompted for it, fails if that is not possible.
+///
+/// * `initial`: Version given on the command line
+/// * `interactive`: Whether the user can answer prompts
+pub fn given_initial_version(
+    initial: Option<&str>,
+    interactive: bool,
+) -> Result<Option<Version>, String> {
+    match initial {
+        Some(initial) => initial.parse().map(Some),
+        None if interactive => Ok(None),
+        None => {
+            Err("Not running in a terminal, specify the first version with --initial.".to_owned())
+        }
+    }
+}
+
+/// Prompt the user for the first version and tag prefix of a repository without semantic version
+/// tags. Returns the version including its prefix.
+///
+/// * `ask_prefix`: Whether to ask for the tag prefix, otherwise the version has no prefix
+pub fn prompt_initial_version(ask_prefix: bool) -> Result<Version, InquireError> {
+    const CUSTOM: &str = "Custom";
+    let options = vec!["0.1.0", "1.0.0", CUSTOM];
+
+    let answer = Select::new("Which version to start with?", options).prompt()?;
+    let mut version: Version = if answer == CUSTOM {
+        Text::new("Initial version:")
+            .with_validator(|input: &str| {
+                Ok(match input.parse::<Version>() {
+                    Ok(version) if version.prefix.is_empty() => Validation::Valid,
+                    Ok(_) => Validation::Invalid("Enter the version without prefix.".into()),
+                    Err(e) => Validation::Invalid(e.into()),
+                })
+            })
+            .prompt()?
+            .parse()
+            // Safety: Input is validated to be a version
+            .unwrap()
+    } else {
+        // Safety: Options are valid versions
+        answer.parse().unwrap()
+    };
+
+    if ask_prefix {
+        let prefixes = vec!["v", "none"];
+        let prefix = Select::new("Which tag prefix to use?", prefixes).prompt()?;
+        if prefix != "none" {
+            version.prefix = prefix.to_owned();
+        }
+    }
+
+    Ok(version)
+}
+
+/// Bump the version segments according to the selected bump. Pre-release identifiers and build
+/// metadata of the previous version are dropped unless a pre-release is continued. A pre-release
+/// whose version already contains the bump, e.g. `1.3.0-rc.1` for a minor bump, is promoted to its
+/// release, or continued if its channel is given.
 ///
 /// # Arguments
-/// * `major`: Mutable reference to major version
-/// * `minor`: Mutable reference to minor version
-/// * `patch`: Mutable reference to patch version
+/// * `version`: Mutable reference to the version to bump
 /// * `bump`: Specification of which segment to bump
-pub fn semver_bump(major: &mut u32, minor: &mut u32, patch: &mut u32, bump: &Type) {
+/// * `channel`: Pre-release channel to start or switch to, e.g. `alpha`, `beta` or `rc`
+pub fn semver_bump(
+    version: &mut Version,
+    bump: &Type,
+    channel: Option<&str>,
+) -> Result<(), String> {
     debug!("Bumping {}.", bump);
+    if let Some(channel) = channel {
+        // Safety: Regex is verified to be valid
+        if !Regex::new(CHANNEL_REGEX).unwrap().is_match(channel) {
+            return Err(format!("Invalid pre-release channel: {}", channel));
+        }
+    }
+
+    let previous = version.clone();
+    version.build.clear();
+    // The pending release of a pre-release already bumps the requested element
+    let pending = previous.is_prerelease()
+        && match bump {
+            Type::Major => previous.minor == 0 && previous.patch == 0,
+            Type::Minor => previous.patch == 0,
+            Type::Patch => true,
+            Type::Prerelease | Type::Release => false,
+        };
     match bump {
+        _ if pending => (),
         Type::Major => {
-            *major += 1;
-            *minor = 0;
-
-            *patch = 0;
+            version.major += 1;
+            version.minor = 0;
+            version.patch = 0;
         }
         Type::Minor => {
-            *minor += 1;
-            *patch = 0;
+            version.minor += 1;
+            version.patch = 0;
         }
-        Type::Patch => *patch += 1,
+        Type::Patch => version.patch += 1,
+        Type::Prerelease => {
+            if !previous.is_prerelease() {
+                return Err(format!(
+                    "{} is not a pre-release, start one with a version bump and a channel.",
+                    previous
+                ));
+            }
+            match channel {
+                Some(channel) if previous.channel() != Some(channel) => {
+                    version.pre = vec![channel.into(), Identifier::Numeric(1)];
+                }
+                _ => increment_prerelease(&mut version.pre),
+            }
+        }
+        Type::Release => {
+            if !previous.is_prerelease() {
+                return Err(format!("{} is already a release.", previous));
+            }
+            if channel.is_some() {
+                return Err("A release cannot be combined with a pre-release channel.".to_owned());
+            }
+            version.pre.clear();
+        }
+    }
+
+    match (bump, channel) {
+        (Type::Major | Type::Minor | Type::Patch, Some(channel))
+            if pending && previous.channel() == Some(channel) =>
+        {
+            increment_prerelease(&mut version.pre);
+        }
+        (Type::Major | Type::Minor | Type::Patch, Some(channel)) => {
+            version.pre = vec![channel.into(), Identifier::Numeric(1)];
+        }
+        (Type::Major | Type::Minor | Type::Patch, None) => version.pre.clear(),
+        _ => (),
+    }
+
+    if version.cmp_precedence(&previous) != Ordering::Greater {
+        return Err(format!(
+            "{} does not have a higher precedence than {}.",
+            version, previous
+        ));
+    }
+    Ok(())
+}
+
+/// Increment the last numeric pre-release identifier, or append `1` if there is none.
+fn increment_prerelease(pre: &mut Vec<Identifier>) {
+    match pre
+        .iter_mut()
+        .rev()
+        .find_map(|identifier| match identifier {
+            Identifier::Numeric(n) => Some(n),
+            Identifier::AlphaNumeric(_) => None,
+        }) {
+        Some(n) => *n += 1,
+        None => pre.push(Identifier::Numeric(1)),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::{commit, commit_file, init_repo};
+    use std::path::Path;
+    use tempfile::TempDir;
 
     #[test]
     fn test_semver_bump_major() {
-        let mut major = 1;
-        let mut minor = 2;
-        let mut patch = 3;
+        let mut version: Version = "1.2.3".parse().unwrap();
         let bump = Type::Major;
 
-        semver_bump(&mut major, &mut minor, &mut patch, &bump);
+        semver_bump(&mut version, &bump, None).unwrap();
 
-        assert_eq!(major, 2);
-        assert_eq!(minor, 0);
-        assert_eq!(patch, 0);
+        assert_eq!(version.to_string(), "2.0.0");
     }
 
     #[test]
     fn test_semver_bump_minor() {
-        let mut major = 1;
-        let mut minor = 2;
-        let mut patch = 3;
+        let mut version: Version = "1.2.3".parse().unwrap();
         let bump = Type::Minor;
 
-        semver_bump(&mut major, &mut minor, &mut patch, &bump);
+        semver_bump(&mut version, &bump, None).unwrap();
 
-        assert_eq!(major, 1);
-        assert_eq!(minor, 3);
-        assert_eq!(patch, 0);
+        assert_eq!(version.to_string(), "1.3.0");
     }
 
     #[test]
     fn test_semver_bump_patch() {
-        let mut major = 1;
-        let mut minor = 2;
-        let mut patch = 3;
+        let mut version: Version = "1.2.3".parse().unwrap();
         let bump = Type::Patch;
 
-        semver_bump(&mut major, &mut minor, &mut patch, &bump);
+        semver_bump(&mut version, &bump, None).unwrap();
+
+        assert_eq!(version.to_string(), "1.2.4");
+    }
+
+    #[test]
+    fn test_semver_bump_keeps_prefix() {
+        let mut version: Version = "v1.2.3".parse().unwrap();
+
+        semver_bump(&mut version, &Type::Minor, None).unwrap();
+
+        assert_eq!(version.to_string(), "v1.3.0");
+    }
+
+    fn bumped(version: &str, bump: Type, channel: Option<&str>) -> Result<String, String> {
+        let mut version: Version = version.parse().unwrap();
+        semver_bump(&mut version, &bump, channel).map(|_| version.to_string())
+    }
+
+    #[test]
+    fn test_semver_bump_drops_prerelease() {
+        assert_eq!(
+            bumped("v1.2.3-rc.1+b.3", Type::Minor, None).unwrap(),
+            "v1.3.0"
+        );
+        assert_eq!(
+            bumped("v1.2.0-rc.1+b.3", Type::Major, None).unwrap(),
+            "v2.0.0"
+        );
+    }
 
-        assert_eq!(major, 1);
-        assert_eq!(minor, 2);
-        assert_eq!(patch, 4);
+    #[test]
+    fn test_semver_bump_promotes_pending_prerelease() {
+        assert_eq!(
+            bumped("v1.3.0-rc.1+b.3", Type::Minor, None).unwrap(),
+            "v1.3.0"
+        );
+        assert_eq!(bumped("1.3.0-rc.1", Type::Patch, None).unwrap(), "1.3.0");
+        assert_eq!(bumped("2.0.0-beta.2", Type::Minor, None).unwrap(), "2.0.0");
     }
 
     #[test]
-    fn test_split_tag_semver_valid() {
-        let tag = "v1.2.3-alpha";
-        let result = split_tag_semver(tag);
+    fn test_semver_bump_continues_pending_prerelease() {
+        assert_eq!(
+            bumped("1.3.0-rc.1", Type::Minor, Some("rc")).unwrap(),
+            "1.3.0-rc.2"
+        );
+        assert_eq!(
+            bumped("1.3.0-beta.3", Type::Patch, Some("rc")).unwrap(),
+            "1.3.0-rc.1"
+        );
+        assert_eq!(
+            bumped("1.3.0-rc.1", Type::Major, Some("rc")).unwrap(),
+            "2.0.0-rc.1"
+        );
+    }
 
-        assert_eq!(result, Some(("v".to_owned(), 1, 2, 3, "-alpha".to_owned())));
+    #[test]
+    fn test_semver_bump_start_prerelease() {
+        assert_eq!(
+            bumped("v1.2.0", Type::Minor, Some("rc")).unwrap(),
+            "v1.3.0-rc.1"
+        );
+        assert!(bumped("v1.2.0", Type::Minor, Some("1")).is_err());
     }
 
     #[test]
-    fn test_split_tag_semver_invalid() {
-        // Invalid tag format
-        let tag = "invalid-tag";
-        let result = split_tag_semver(tag);
+    fn test_semver_bump_next_prerelease() {
+        assert_eq!(
+            bumped("1.3.0-rc.1", Type::Prerelease, None).unwrap(),
+            "1.3.0-rc.2"
+        );
+        assert_eq!(
+            bumped("1.3.0-beta", Type::Prerelease, None).unwrap(),
+            "1.3.0-beta.1"
+        );
+        assert!(bumped("1.3.0", Type::Prerelease, None).is_err());
+    }
 
-        assert_eq!(result, None);
+    #[test]
+    fn test_semver_bump_switch_channel() {
+        assert_eq!(
+            bumped("1.3.0-beta.4", Type::Prerelease, Some("rc")).unwrap(),
+            "1.3.0-rc.1"
+        );
+        // Switching back to an earlier channel would decrease precedence
+        assert!(bumped("1.3.0-rc.1", Type::Prerelease, Some("alpha")).is_err());
     }
 
     #[test]
-    fn test_split_tag_semver_no_prefix() {
-        // No prefix
-        let tag = "1.2.3-beta";
-        let result = split_tag_semver(tag);
+    fn test_semver_bump_promote_release() {
+        assert_eq!(bumped("1.3.0-rc.2", Type::Release, None).unwrap(), "1.3.0");
+        assert!(bumped("1.3.0", Type::Release, None).is_err());
+        assert!(bumped("1.3.0-rc.2", Type::Release, Some("rc")).is_err());
+    }
+
+    /// Create an annotated tag on HEAD.
+    fn create_tag(repo: &Repository, tag_name: &str) {
+        let tag = prepare_new_tag(repo, tag_name).unwrap();
+        create_new_tag(repo, &tag).unwrap();
+    }
+
+    /// Create a commit with the given parents without moving HEAD.
+    fn commit_on(repo: &Repository, parents: &[Oid], message: &str) -> Oid {
+        let signature = repo.signature().unwrap();
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        let parents: Vec<Commit> = parents
+            .iter()
+            .map(|oid| repo.find_commit(*oid).unwrap())
+            .collect();
+        let parents: Vec<&Commit> = parents.iter().collect();
+        repo.commit(None, &signature, &signature, message, &tree, &parents)
+            .unwrap()
+    }
+
+    /// Tag the given commit with a lightweight tag.
+    fn tag_commit(repo: &Repository, oid: Oid, tag_name: &str) {
+        let object = repo.find_object(oid, None).unwrap();
+        repo.tag_lightweight(tag_name, &object, false).unwrap();
+    }
+
+    /// Create a history where a hotfix tag is nearer to HEAD than a higher release:
+    ///
+    /// ```text
+    /// v1.4.0 - v1.5.0 - D - E - HEAD
+    ///       \                  /
+    ///        fix - v1.4.1 -----
+    ///       \
+    ///        v1.6.0-rc.1 (unmerged)
+    /// ```
+    fn repo_with_hotfix(path: &Path) -> Repository {
+        let repo = init_repo(path);
+        let base = repo.head().unwrap().target().unwrap();
+        tag_commit(&repo, base, "v1.4.0");
+        let fix = commit_on(&repo, &[base], "fix: hotfix");
+        let hotfix = commit_on(&repo, &[fix], "fix: second hotfix");
+        tag_commit(&repo, hotfix, "v1.4.1");
+        let unmerged = commit_on(&repo, &[base], "feat: unmerged");
+        tag_commit(&repo, unmerged, "v1.6.0-rc.1");
+        let release = commit(&repo, "feat: release");
+        tag_commit(&repo, release, "v1.5.0");
+        commit(&repo, "feat: d");
+        let tip = commit(&repo, "feat: e");
+        let merge = commit_on(&repo, &[tip, hotfix], "Merge hotfix");
+        repo.set_head_detached(merge).unwrap();
+        repo
+    }
+
+    #[test]
+    fn test_find_latest_semver_tag_nearest() {
+        let dir = TempDir::new().unwrap();
+        let repo = repo_with_hotfix(dir.path());
+
+        let tag =
+            find_latest_semver_tag(&repo, &TagFilter::new("*"), TagSelection::Nearest).unwrap();
+        assert_eq!(tag.as_deref(), Some("v1.4.1"));
+    }
+
+    #[test]
+    fn test_find_latest_semver_tag_highest_reachable() {
+        let dir = TempDir::new().unwrap();
+        let repo = repo_with_hotfix(dir.path());
+
+        let tag =
+            find_latest_semver_tag(&repo, &TagFilter::new("*"), TagSelection::HighestReachable)
+                .unwrap();
+        assert_eq!(tag.as_deref(), Some("v1.5.0"));
+    }
+
+    #[test]
+    fn test_find_latest_semver_tag_highest() {
+        let dir = TempDir::new().unwrap();
+        let repo = repo_with_hotfix(dir.path());
+
+        let tag =
+            find_latest_semver_tag(&repo, &TagFilter::new("*"), TagSelection::Highest).unwrap();
+        assert_eq!(tag.as_deref(), Some("v1.6.0-rc.1"));
+    }
+
+    #[test]
+    fn test_find_latest_semver_tag_same_commit() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let head = repo.head().unwrap().target().unwrap();
+        tag_commit(&repo, head, "v2.0.0");
+        tag_commit(&repo, head, "v2.0.0-rc.2");
+        tag_commit(&repo, head, "v1.9.9");
+
+        let tag =
+            find_latest_semver_tag(&repo, &TagFilter::new("*"), TagSelection::Nearest).unwrap();
+        assert_eq!(tag.as_deref(), Some("v2.0.0"));
+    }
+
+    #[test]
+    fn test_find_latest_semver_tag_filtered() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let release = repo.head().unwrap().target().unwrap();
+        tag_commit(&repo, release, "v1.2.3");
+        let dependency = commit(&repo, "chore: bump openssl");
+        tag_commit(&repo, dependency, "deps/openssl-3.0.8");
+        let build = commit(&repo, "chore: nightly");
+        tag_commit(&repo, build, "build-2023.10.01");
+        tag_commit(&repo, build, "nightly-1.0.0");
+
+        let filter = TagFilter {
+            exclude: Some(Regex::new("^(deps/|nightly-)").unwrap()),
+            ..TagFilter::new("*[0-9]*.[0-9]*.[0-9]*")
+        };
+        let tag = find_latest_semver_tag(&repo, &filter, TagSelection::Nearest).unwrap();
+        assert_eq!(tag.as_deref(), Some("v1.2.3"));
+    }
+
+    #[test]
+    fn test_find_latest_semver_tag_components() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let first = repo.head().unwrap().target().unwrap();
+        tag_commit(&repo, first, "web/v3.0.1");
+        tag_commit(&repo, first, "v0.9.0");
+        let second = commit(&repo, "feat(api): new endpoint");
+        tag_commit(&repo, second, "api/v1.2.3");
+
+        let web = TagFilter::new("web/v[0-9]*.[0-9]*.[0-9]*");
+        let tag = find_latest_semver_tag(&repo, &web, TagSelection::Nearest).unwrap();
+        assert_eq!(tag.as_deref(), Some("web/v3.0.1"));
+
+        let root = TagFilter {
+            excluded_prefixes: vec!["api/v".to_owned(), "web/v".to_owned()],
+            ..TagFilter::new("*[0-9]*.[0-9]*.[0-9]*")
+        };
+        let tag = find_latest_semver_tag(&repo, &root, TagSelection::Nearest).unwrap();
+        assert_eq!(tag.as_deref(), Some("v0.9.0"));
+    }
+
+    #[test]
+    fn test_commits_touching_paths() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "api/main.rs", "fn main() {}", "feat(api): endpoint");
+        commit_file(&repo, "web/index.html", "<html>", "feat(web): page");
+        commit_file(&repo, "api/lib.rs", "", "fix(api): crash");
+
+        let commits = commits_since(&repo, None).unwrap();
+        let api = commits_touching(&repo, commits, &["api/".to_owned()]).unwrap();
+        let summaries: Vec<&str> = api.iter().map(|c| c.summary().unwrap()).collect();
+        assert_eq!(summaries, vec!["fix(api): crash", "feat(api): endpoint"]);
+
+        let commits = commits_since(&repo, None).unwrap();
+        assert_eq!(commits_touching(&repo, commits, &[]).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_commits_outside_skipped_tags() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let release = commit(&repo, "feat: stable");
+        tag_commit(&repo, release, "v0.9.0");
+        let beta = commit(&repo, "feat: preview");
+        tag_commit(&repo, beta, "v1.0.0-beta.1");
+        commit(&repo, "fix: after beta");
+
+        let commits = commits_since(&repo, Some("v0.9.0")).unwrap();
+        let skip = Regex::new("beta").unwrap();
+        let outside = commits_outside_tags(&repo, commits, &skip).unwrap();
+        let summaries: Vec<&str> = outside.iter().map(|c| c.summary().unwrap()).collect();
+        assert_eq!(summaries, vec!["fix: after beta"]);
+    }
+
+    #[test]
+    fn test_paths_changed_since() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let release = commit_file(&repo, "api/main.rs", "fn main() {}", "feat(api): endpoint");
+        tag_commit(&repo, release, "api/v1.0.0");
+        commit_file(&repo, "web/index.html", "<html>", "feat(web): page");
+
+        let api = ["api/".to_owned()];
+        assert!(!paths_changed_since(&repo, Some("api/v1.0.0"), &api).unwrap());
+        assert!(paths_changed_since(&repo, Some("api/v1.0.0"), &["web/".to_owned()]).unwrap());
+        assert!(paths_changed_since(&repo, None, &api).unwrap());
+    }
+
+    #[test]
+    fn test_create_release_commit() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let previous = commit_file(&repo, "VERSION", "1.0.0\n", "chore: add version");
+        // Staged changes of other files are not part of the release commit
+        std::fs::write(dir.path().join("notes.txt"), "draft").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("notes.txt")).unwrap();
+        index.write().unwrap();
+
+        let update = FileUpdate {
+            path: "VERSION".to_owned(),
+            old: "1.0.0\n".to_owned(),
+            new: "1.1.0\n".to_owned(),
+        };
+        let updates = [update];
+        let release = create_release_commit(&repo, &updates, "chore(release): v1.1.0").unwrap();
+        assert_eq!(repo.head().unwrap().target(), Some(previous));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("VERSION")).unwrap(),
+            "1.0.0\n"
+        );
+
+        checkout_release_commit(&repo, &release, &updates).unwrap();
+        assert_eq!(repo.head().unwrap().target(), Some(release.id()));
+        assert_eq!(release.parent_id(0).unwrap(), previous);
+        assert_eq!(release.message(), Some("chore(release): v1.1.0"));
+        let tree = release.tree().unwrap();
+        let blob = tree
+            .get_name("VERSION")
+            .unwrap()
+            .to_object(&repo)
+            .unwrap()
+            .peel_to_blob()
+            .unwrap();
+        assert_eq!(blob.content(), b"1.1.0\n");
+        assert!(tree.get_name("notes.txt").is_none());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("VERSION")).unwrap(),
+            "1.1.0\n"
+        );
+        assert!(repo.status_file(Path::new("VERSION")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_create_release_commit_untracked() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let update = FileUpdate {
+            path: "docs/VERSION".to_owned(),
+            old: String::new(),
+            new: "1.1.0".to_owned(),
+        };
+
+        let updates = [update];
+        let release = create_release_commit(&repo, &updates, "chore(release): v1.1.0").unwrap();
+        checkout_release_commit(&repo, &release, &updates).unwrap();
+        let entry = release
+            .tree()
+            .unwrap()
+            .get_path(Path::new("docs/VERSION"))
+            .unwrap();
+        assert_eq!(entry.filemode(), 0o100644);
+        assert_eq!(
+            entry
+                .to_object(&repo)
+                .unwrap()
+                .peel_to_blob()
+                .unwrap()
+                .content(),
+            b"1.1.0"
+        );
+        assert!(repo
+            .status_file(Path::new("docs/VERSION"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_create_release_commit_ignored() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(
+            &repo,
+            ".gitignore",
+            "/Cargo.lock\n",
+            "chore: ignore lockfile",
+        );
+        let previous = repo.head().unwrap().target().unwrap();
+        let updates = [FileUpdate {
+            path: "Cargo.lock".to_owned(),
+            old: String::new(),
+            new: "version = 3\n".to_owned(),
+        }];
+
+        let error = create_release_commit(&repo, &updates, "chore(release): v1.1.0").unwrap_err();
+        assert_eq!(
+            error.message(),
+            "Cargo.lock is ignored by git and not added to the release commit"
+        );
+        assert_eq!(repo.head().unwrap().target().unwrap(), previous);
+    }
+
+    #[test]
+    fn test_find_latest_semver_tag_none() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+
+        for selection in [
+            TagSelection::Nearest,
+            TagSelection::HighestReachable,
+            TagSelection::Highest,
+        ] {
+            assert_eq!(
+                find_latest_semver_tag(&repo, &TagFilter::new("*"), selection),
+                Ok(None)
+            );
+        }
+    }
+
+    #[test]
+    fn test_requires_confirmation() {
+        assert_eq!(requires_confirmation(false, true), Ok(true));
+        assert_eq!(requires_confirmation(true, true), Ok(false));
+        assert_eq!(requires_confirmation(true, false), Ok(false));
+        assert_eq!(
+            requires_confirmation(false, false),
+            Err("Not running in a terminal, confirm the tag creation with --yes.".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_initial_version_without_tags() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let tag =
+            find_latest_semver_tag(&repo, &TagFilter::new("*"), TagSelection::Nearest).unwrap();
+        assert_eq!(tag, None);
+
+        let version = given_initial_version(Some("v0.1.0"), false).unwrap();
+        assert_eq!(version.map(|v| v.to_string()).as_deref(), Some("v0.1.0"));
+        assert!(given_initial_version(Some("first"), false).is_err());
+        // Without --initial the user is prompted, which requires a terminal
+        assert_eq!(given_initial_version(None, true), Ok(None));
+        assert_eq!(
+            given_initial_version(None, false),
+            Err("Not running in a terminal, specify the first version with --initial.".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_default_branch_from_remote_head() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let head = repo.head().unwrap().target().unwrap();
+        repo.remote("upstream", "https://example.com/upstream.git")
+            .unwrap();
+        repo.remote("origin", "https://example.com/origin.git")
+            .unwrap();
+        repo.reference("refs/remotes/upstream/develop", head, false, "test")
+            .unwrap();
+        repo.reference("refs/remotes/origin/trunk", head, false, "test")
+            .unwrap();
+        repo.reference_symbolic(
+            "refs/remotes/upstream/HEAD",
+            "refs/remotes/upstream/develop",
+            false,
+            "test",
+        )
+        .unwrap();
+        repo.reference_symbolic(
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/trunk",
+            false,
+            "test",
+        )
+        .unwrap();
+
+        assert_eq!(default_branch(&repo).as_deref(), Some("trunk"));
+    }
+
+    #[test]
+    fn test_default_branch_from_config() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let mut config = repo.config().unwrap();
+
+        config.set_str("init.defaultBranch", "develop").unwrap();
+        assert_eq!(default_branch(&repo), None);
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("develop", &head, false).unwrap();
+        assert_eq!(default_branch(&repo).as_deref(), Some("develop"));
+    }
+
+    #[test]
+    fn test_default_branch_from_config_remote_tracking() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let head = repo.head().unwrap().target().unwrap();
+        repo.config()
+            .unwrap()
+            .set_str("init.defaultBranch", "trunk")
+            .unwrap();
+        repo.remote("origin", "https://example.com/origin.git")
+            .unwrap();
+
+        assert_eq!(default_branch(&repo), None);
+
+        repo.reference("refs/remotes/origin/trunk", head, false, "test")
+            .unwrap();
+        assert_eq!(default_branch(&repo).as_deref(), Some("trunk"));
+    }
+
+    #[test]
+    fn test_release_branch_policy() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let policies = vec![BranchPolicy::new("release/*")];
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+
+        assert_eq!(release_branch_policy(&repo, &policies), None);
+
+        repo.branch("release/1.x", &head, false).unwrap();
+        repo.set_head("refs/heads/release/1.x").unwrap();
+
+        assert_eq!(release_branch_policy(&repo, &policies), Some(&policies[0]));
+    }
+
+    #[test]
+    fn test_release_branch_policy_detached_head() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let policies = vec![BranchPolicy::new("main")];
+        let released = commit(&repo, "feat: released");
+        let tip = commit(&repo, "fix: on top");
+
+        // Only a remote-tracking release branch contains the commit
+        repo.reference("refs/remotes/origin/main", tip, false, "test")
+            .unwrap();
+        repo.remote("origin", "https://example.com/repo.git")
+            .unwrap();
+        repo.set_head_detached(released).unwrap();
+
+        assert_eq!(release_branch_policy(&repo, &policies), Some(&policies[0]));
+
+        // A commit on another branch is not contained in the release branch
+        let other = repo.find_commit(released).unwrap();
+        repo.branch("feature", &other, false).unwrap();
+        repo.set_head("refs/heads/feature").unwrap();
+        let feature = commit(&repo, "feat: unreleased");
+        repo.set_head_detached(feature).unwrap();
+
+        assert_eq!(release_branch_policy(&repo, &policies), None);
+    }
+
+    #[test]
+    fn test_prepare_new_tag() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let head = commit(&repo, "feat: something");
+
+        let tag = prepare_new_tag(&repo, "v1.0.0").unwrap();
+
+        assert_eq!(tag.name, "v1.0.0");
+        assert_eq!(tag.target.id(), head);
+        assert_eq!(tag.tagger.name(), Some("Taggr Test"));
+        assert!(repo.tag_names(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_create_signed_tag_ssh() {
+        let dir = TempDir::new().unwrap();
+        let key = dir.path().join("id_ed25519");
+        let generated = std::process::Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+            .arg(&key)
+            .status();
+        if !generated.map(|status| status.success()).unwrap_or(false) {
+            // ssh-keygen is not available on this machine
+            return;
+        }
+        let repo = init_repo(&dir.path().join("repo"));
+        let mut config = repo.config().unwrap();
+        config.set_str("gpg.format", "ssh").unwrap();
+        config
+            .set_str("user.signingkey", key.to_str().unwrap())
+            .unwrap();
+
+        let mut tag = prepare_new_tag(&repo, "v1.0.0").unwrap();
+        tag.sign = true;
+        create_new_tag(&repo, &tag).unwrap();
+
+        let created = repo
+            .find_reference("refs/tags/v1.0.0")
+            .unwrap()
+            .peel_to_tag()
+            .unwrap();
+        assert_eq!(created.target_id(), tag.target.id());
+        assert!(created
+            .message()
+            .unwrap()
+            .contains("-----BEGIN SSH SIGNATURE-----"));
+    }
+
+    #[test]
+    fn test_push_tag() {
+        let dir = TempDir::new().unwrap();
+        let remote = Repository::init_bare(dir.path().join("remote.git")).unwrap();
+        let repo = init_repo(&dir.path().join("local"));
+        repo.remote("origin", dir.path().join("remote.git").to_str().unwrap())
+            .unwrap();
+
+        create_tag(&repo, "v1.0.0");
+        push_tag(&repo, "origin", "v1.0.0", None).unwrap();
+
+        assert!(remote.find_reference("refs/tags/v1.0.0").is_ok());
+    }
+
+    #[test]
+    fn test_push_tag_with_branch() {
+        let dir = TempDir::new().unwrap();
+        let remote = Repository::init_bare(dir.path().join("remote.git")).unwrap();
+        let repo = init_repo(&dir.path().join("local"));
+        repo.remote("origin", dir.path().join("remote.git").to_str().unwrap())
+            .unwrap();
+        let branch = repo.head().unwrap().shorthand().unwrap().to_owned();
+
+        create_tag(&repo, "v1.0.0");
+        push_tag(&repo, "origin", "v1.0.0", Some(&branch)).unwrap();
+
+        assert!(remote.find_reference("refs/tags/v1.0.0").is_ok());
+        assert_eq!(
+            remote
+                .find_reference(&format!("refs/heads/{}", branch))
+                .unwrap()
+                .target(),
+            repo.head().unwrap().target()
+        );
+    }
+
+    #[test]
+    fn test_push_tag_rejected() {
+        let dir = TempDir::new().unwrap();
+        Repository::init_bare(dir.path().join("remote.git")).unwrap();
+        let url = dir.path().join("remote.git");
+
+        // Somebody else already pushed the same tag on another commit
+        let other = init_repo(&dir.path().join("other"));
+        other.remote("origin", url.to_str().unwrap()).unwrap();
+        create_tag(&other, "v1.0.0");
+        push_tag(&other, "origin", "v1.0.0", None).unwrap();
+
+        let repo = init_repo(&dir.path().join("local"));
+        repo.remote("origin", url.to_str().unwrap()).unwrap();
+        commit(&repo, "feat: diverge");
+        create_tag(&repo, "v1.0.0");
 
-        assert_eq!(result, Some(("".to_owned(), 1, 2, 3, "-beta".to_owned())));
+        assert!(push_tag(&repo, "origin", "v1.0.0", None).is_err());
+        assert!(repo.find_reference("refs/tags/v1.0.0").is_ok());
     }
 
     #[test]
-    fn test_split_tag_semver_no_suffix() {
-        // No prefix
-        let tag = "v1.2.3";
-        let result = split_tag_semver(tag);
+    fn test_push_tag_unknown_remote() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        create_tag(&repo, "v1.0.0");
 
-        assert_eq!(result, Some(("v".to_owned(), 1, 2, 3, "".to_owned())));
+        assert!(push_tag(&repo, "origin", "v1.0.0", None).is_err());
     }
 }
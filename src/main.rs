@@ -1,33 +1,467 @@
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
 use git2::Repository;
-use log::{error, info};
+use log::{debug, error, info};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+mod cargo;
+mod changelog;
+mod cliff;
+mod config;
+mod conventional;
 mod elements;
 mod functions;
+mod manifest;
+mod message;
+mod preflight;
+mod signing;
+mod status;
+#[cfg(test)]
+mod test_utils;
+mod workspace;
+use cargo::CargoWorkspace;
+use changelog::{update_changelog, CommitRules};
+use config::{BranchPolicy, Config};
+use elements::{TagSelection, Type, Version};
 use functions::*;
+use git2::Commit;
+use manifest::{apply_replacements, update_manifests, FileUpdate};
+use message::{
+    edit_message, render_message, MessageContext, DEFAULT_COMMIT_MESSAGE_TEMPLATE,
+    DEFAULT_MESSAGE_TEMPLATE,
+};
+use preflight::{
+    check_clean_working_tree, check_head_untagged, check_new_commits, check_tag_available,
+    check_upstream_in_sync,
+};
+use status::{format_status, ComponentStatus};
+use workspace::{check_workspace_config, format_releases, plan_releases};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Directory of git repository to tag. Defaults to current directory
     work_dir: Option<String>,
 
     /// Turn debugging information on
-    #[arg(short, long, action = clap::ArgAction::Count)]
+    #[arg(global = true, short, long, action = clap::ArgAction::Count)]
     debug: u8,
 
-    /// Force working on another branch than master or main
-    #[arg(short, long)]
+    /// Force working on another branch than the release branches, the default branch of the
+    /// repository if none are configured, and ignore their policies
+    #[arg(global = true, short, long)]
     force: bool,
+
+    /// Tag even if the working tree has modified, staged or untracked files
+    #[arg(global = true, long)]
+    allow_dirty: bool,
+
+    /// Tag even if the checked out branch is ahead of or behind its upstream branch
+    #[arg(global = true, long)]
+    allow_unsynced: bool,
+
+    /// Tag even if HEAD already has a version tag or there are no commits since the last tag
+    #[arg(long)]
+    allow_empty: bool,
+
+    /// Component of a monorepo to tag, using its own tag prefix and version stream
+    #[arg(short, long, value_name = "NAME")]
+    component: Option<String>,
+
+    /// Strategy to select the tag of the last version
+    #[arg(global = true, long, value_enum, value_name = "STRATEGY")]
+    tag_selection: Option<TagSelection>,
+
+    /// Version element to bump, skips the interactive selection
+    #[arg(global = true, short, long, value_enum)]
+    bump: Option<Type>,
+
+    /// Start or switch to a pre-release on the given channel, e.g. alpha, beta or rc
+    #[arg(short, long, value_name = "CHANNEL")]
+    pre: Option<String>,
+
+    /// Infer the version bump from Conventional Commits since the last tag
+    #[arg(short, long)]
+    auto: bool,
+
+    /// Version of the first tag if the repository has no semantic version tag yet, e.g. v0.1.0
+    #[arg(short, long, value_name = "VERSION")]
+    initial: Option<String>,
+
+    /// Push the new tag to the given remote, written as --push=REMOTE. Defaults to origin
+    #[arg(
+        global = true,
+        long,
+        value_name = "REMOTE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "origin",
+        overrides_with = "no_push"
+    )]
+    push: Option<String>,
+
+    /// Do not push the new tag, regardless of the configured remote
+    #[arg(global = true, long, overrides_with = "push")]
+    no_push: bool,
+
+    /// Template of the tag message. Supports the placeholders {version}, {previous}, {bump},
+    /// {commit_count}, {date} and {commits}
+    #[arg(global = true, short, long, value_name = "TEMPLATE")]
+    message: Option<String>,
+
+    /// Edit the rendered tag message in the configured editor before creating the tag
+    #[arg(short, long)]
+    edit: bool,
+
+    /// Sign the tag, regardless of the tag.gpgSign git configuration
+    #[arg(global = true, short, long, overrides_with = "no_sign")]
+    sign: bool,
+
+    /// Do not sign the tag, regardless of the tag.gpgSign git configuration
+    #[arg(global = true, long, overrides_with = "sign")]
+    no_sign: bool,
+
+    /// Update the version in the given manifest before tagging, e.g. Cargo.toml, package.json,
+    /// pyproject.toml, Chart.yaml or VERSION. The changes are committed and the tag is placed on
+    /// the release commit
+    #[arg(long, value_name = "PATH")]
+    manifest: Vec<String>,
+
+    /// Prepend the release notes, grouped by commit type, to the given changelog in the release
+    /// commit, written as --changelog=PATH. Defaults to CHANGELOG.md
+    #[arg(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "CHANGELOG.md"
+    )]
+    changelog: Option<String>,
+
+    /// Print the tag that would be created without writing anything to the repository
+    #[arg(global = true, long)]
+    dry_run: bool,
+
+    /// Create the tag without asking for confirmation
+    #[arg(global = true, short, long)]
+    yes: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the configured components with their last version, whether they changed, the number
+    /// of commits changing them and the suggested bump
+    Status,
+    /// Tag every crate of the Cargo workspace in the repository root that changed since its last
+    /// tag with its own <crate>-v<version> tag. Dependents of bumped crates get a patch bump
+    Workspace,
+}
+
+/// Reject combinations of arguments that depend on their values or the subcommand, which clap
+/// cannot declare.
+fn check_arguments(cli: &Cli) -> Result<(), clap::Error> {
+    if cli.bump == Some(Type::Release) && cli.pre.is_some() {
+        return Err(Cli::command().error(
+            ErrorKind::ArgumentConflict,
+            "--bump release promotes a pre-release and cannot be used with --pre",
+        ));
+    }
+    if let Some(Command::Workspace) = cli.command {
+        let unsupported = [
+            ("--changelog", cli.changelog.is_some()),
+            ("--manifest", !cli.manifest.is_empty()),
+            ("--component", cli.component.is_some()),
+            ("--pre", cli.pre.is_some()),
+            ("--edit", cli.edit),
+            ("--initial", cli.initial.is_some()),
+        ];
+        if let Some((arg, _)) = unsupported.iter().find(|(_, used)| *used) {
+            return Err(Cli::command().error(
+                ErrorKind::ArgumentConflict,
+                format!("{} cannot be used with the workspace subcommand", arg),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Log the given message as error and exit with a non-zero status code.
+fn abort(message: &str) -> ! {
+    error!("{}", message);
+    std::process::exit(1);
+}
+
+/// Remote to push new tags to, given on the command line or configured.
+fn push_remote(cli: &Cli, config: &Config) -> Option<String> {
+    match cli.no_push {
+        true => None,
+        false => cli.push.clone().or(config.push.clone()),
+    }
+}
+
+/// Create the given tags, committing the file updates first if there are any. HEAD is only moved to
+/// the release commit once every tag exists, a failed tag leaves the repository unchanged. Returns
+/// the branch of the release commit.
+fn create_release_tags<'r>(
+    repo: &'r Repository,
+    tags: &mut [NewTag<'r>],
+    updates: &[FileUpdate],
+    commit_message: &str,
+) -> Option<String> {
+    let commit = match updates.is_empty() {
+        true => None,
+        false => Some(
+            create_release_commit(repo, updates, commit_message).unwrap_or_else(|e| {
+                abort(&format!("Could not create release commit: {}", e.message()))
+            }),
+        ),
+    };
+    if let Some(commit) = &commit {
+        for tag in tags.iter_mut() {
+            tag.target = commit.clone();
+        }
+    }
+
+    for (i, tag) in tags.iter().enumerate() {
+        if let Err(e) = create_new_tag(repo, tag) {
+            for created in &tags[..i] {
+                if let Err(e) = repo.tag_delete(&created.name) {
+                    error!("Could not delete tag {}: {}", created.name, e.message());
+                }
+            }
+            let state = match commit {
+                Some(_) => ", HEAD and the working directory were left unchanged",
+                None => "",
+            };
+            abort(&format!(
+                "Could not create new tag {}{}: {}",
+                tag.name,
+                state,
+                e.message()
+            ));
+        }
+    }
+
+    let commit = commit?;
+    checkout_release_commit(repo, &commit, updates).unwrap_or_else(|e| {
+        abort(&format!(
+            "Created the tags on release commit {}, but could not check it out: {}",
+            commit.id(),
+            e.message()
+        ))
+    });
+    repo.head()
+        .ok()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(str::to_owned))
+}
+
+/// Determine the next version by bumping the version of the last tag. Returns the new version and
+/// the applied bump.
+fn bumped_version(
+    cli: &Cli,
+    config: &Config,
+    last_tag: &str,
+    commits: &[Commit],
+    interactive: bool,
+) -> (Version, Type) {
+    let mut version: Version = last_tag
+        .parse()
+        .unwrap_or_else(|e| panic!("Version could not be found in tag: {}", e));
+
+    debug!("Matched the following tag parts: {:?}", version);
+    info!("Last tagged version: {}", version);
+
+    let mut inferred = None;
+    if cli.auto && cli.bump.is_none() {
+        let rules = CommitRules::new(config).unwrap_or_else(|e| abort(&e));
+        match infer_bump(commits, &rules) {
+            Some((bump, reasons)) => {
+                info!("Inferred {} bump from {} commit(s):", bump, reasons.len());
+                for commit in reasons {
+                    info!(
+                        "  {} {}",
+                        &commit.id().to_string()[..7],
+                        commit.summary().unwrap_or_default()
+                    );
+                }
+                inferred = Some(bump);
+            }
+            None => info!(
+                "None of {} commit(s) since {} requires a version bump.",
+                commits.len(),
+                last_tag
+            ),
+        }
+    }
+
+    let bump = match cli.bump {
+        Some(bump) => bump,
+        None if interactive => {
+            prompt_bump_element(inferred.or(config.bump), version.is_prerelease())
+                .unwrap_or_else(|e| abort(&format!("Could not read version bump: {}", e)))
+        }
+        None => inferred.or(config.bump).unwrap_or_else(|| {
+            abort("Not running in a terminal, specify the version bump with --bump or --auto.")
+        }),
+    };
+
+    semver_bump(&mut version, &bump, cli.pre.as_deref()).unwrap_or_else(|e| abort(&e));
+    (version, bump)
+}
+
+/// Determine the first version of a repository without any semantic version tag.
+fn initial_version(cli: &Cli, config: &Config, interactive: bool) -> Version {
+    info!("No semantic version tag found, creating the first release.");
+
+    match given_initial_version(cli.initial.as_deref(), interactive) {
+        Ok(Some(version)) => version,
+        Ok(None) => prompt_initial_version(config.tag_prefix.is_none())
+            .unwrap_or_else(|e| abort(&format!("Could not read initial version: {}", e))),
+        Err(e) => abort(&e),
+    }
+}
+
+/// Release all changed crates of the Cargo workspace in the repository root: bump their versions in
+/// a release commit and tag each of them.
+fn tag_workspace(
+    cli: &Cli,
+    repo: &Repository,
+    config: &Config,
+    policy: Option<&BranchPolicy>,
+    selection: TagSelection,
+    interactive: bool,
+) {
+    check_workspace_config(config).unwrap_or_else(|e| abort(&e));
+    let work_dir = repo
+        .workdir()
+        .unwrap_or_else(|| abort("Cargo workspaces cannot be tagged in a bare repository."));
+    let workspace = CargoWorkspace::find(work_dir, "Cargo.toml").unwrap_or_else(|e| abort(&e));
+    let releases =
+        plan_releases(repo, config, &workspace, selection, cli.bump).unwrap_or_else(|e| abort(&e));
+    if releases.is_empty() {
+        info!("No crate changed since its last tag.");
+        return;
+    }
+    info!("Planned releases:\n{}", format_releases(&releases));
+
+    let mut versions = BTreeMap::new();
+    let mut tags = Vec::new();
+    let template = cli
+        .message
+        .as_deref()
+        .or(config.message.as_deref())
+        .unwrap_or(DEFAULT_MESSAGE_TEMPLATE);
+    for release in &releases {
+        match policy {
+            Some(policy) if !cli.force => policy
+                .check(release.bump, &release.version)
+                .unwrap_or_else(|e| abort(&e)),
+            _ => (),
+        }
+        let new_tag = release.version.to_string();
+        check_tag_available(repo, &new_tag).unwrap_or_else(|e| abort(&e));
+        let bare_version = Version {
+            prefix: String::new(),
+            ..release.version.clone()
+        };
+        versions.insert(release.name.clone(), bare_version.to_string());
+
+        let mut tag = prepare_new_tag(repo, &new_tag)
+            .unwrap_or_else(|e| abort(&format!("Could not prepare new tag: {}", e)));
+        if cli.sign || cli.no_sign {
+            tag.sign = cli.sign;
+        } else if let Some(sign) = config.sign {
+            tag.sign = sign;
+        }
+        let context = MessageContext {
+            version: new_tag,
+            previous: release.previous.clone(),
+            bump: release.bump,
+            commits: release.commits.clone(),
+            date: MessageContext::format_date(&tag.tagger.when()),
+        };
+        tag.message = render_message(template, &context);
+        tags.push(tag);
+    }
+
+    let mut updates = Vec::new();
+    workspace
+        .set_versions(repo, &mut updates, &versions)
+        .unwrap_or_else(|e| abort(&e));
+    updates.retain(|update| update.old != update.new);
+    let tag_names: Vec<&str> = tags.iter().map(|tag| tag.name.as_str()).collect();
+    let commit_message = render_message(
+        config
+            .commit_message
+            .as_deref()
+            .unwrap_or(DEFAULT_COMMIT_MESSAGE_TEMPLATE),
+        &MessageContext {
+            version: tag_names.join(", "),
+            previous: None,
+            bump: None,
+            commits: Vec::new(),
+            date: MessageContext::format_date(&tags[0].tagger.when()),
+        },
+    );
+    if !updates.is_empty() {
+        let diff: Vec<String> = updates.iter().map(|update| update.diff()).collect();
+        info!(
+            "Release commit \"{}\" with the following changes:\n{}",
+            commit_message,
+            diff.join("").trim_end()
+        );
+    }
+
+    let push = push_remote(cli, config);
+    if cli.dry_run {
+        for tag in &tags {
+            info!("Dry run, the following tag would be created:\n{}", tag);
+        }
+        if let Some(remote) = &push {
+            info!("Dry run, the tags would be pushed to {}", remote);
+        }
+        return;
+    }
+
+    if requires_confirmation(cli.yes, interactive).unwrap_or_else(|e| abort(&e)) {
+        let confirmed = confirm_tag_creation(&tag_names.join(", "))
+            .unwrap_or_else(|e| abort(&format!("Could not read confirmation: {}", e)));
+        if !confirmed {
+            info!("Aborting.");
+            return;
+        }
+    }
+
+    let release_branch = create_release_tags(repo, &mut tags, &updates, &commit_message);
+
+    if let Some(remote) = &push {
+        for (i, tag) in tags.iter().enumerate() {
+            // The branch of the release commit only needs to be pushed once
+            let branch = release_branch.as_deref().filter(|_| i == 0);
+            push_tag(repo, remote, &tag.name, branch).unwrap_or_else(|e| {
+                abort(&format!(
+                    "Could not push tag {} to {}, it is only available locally: {}",
+                    tag.name,
+                    remote,
+                    e.message()
+                ))
+            });
+        }
+    }
 }
 
 fn main() {
     let cli = Cli::parse();
+    check_arguments(&cli).unwrap_or_else(|e| e.exit());
     initialize_logging(cli.debug);
 
     // Read repository location or set to working directory
-    let work_dir = match cli.work_dir {
+    let work_dir = match &cli.work_dir {
         Some(dir) => PathBuf::from(dir),
         None => std::env::current_dir().expect("Could not read current working directory."),
     };
@@ -42,22 +476,277 @@ fn main() {
 
     info!("Repository location: {}", &work_dir.as_path().display());
 
-    if !cli.force && !on_master_branch(&repo) {
-        error!("Master/main branch not checked out, aborting.");
+    let config = Config::load(repo.workdir()).unwrap_or_else(|e| abort(&e));
+    debug!("Configuration: {:?}", config);
+    let selection = cli
+        .tag_selection
+        .or(config.tag_selection)
+        .unwrap_or_default();
+
+    if let Some(Command::Status) = cli.command {
+        let components = config
+            .components
+            .as_deref()
+            .unwrap_or_else(|| abort("No components configured."));
+        let statuses: Vec<ComponentStatus> = components
+            .iter()
+            .map(|component| {
+                ComponentStatus::read(&repo, &config, component, selection)
+                    .unwrap_or_else(|e| abort(&e))
+            })
+            .collect();
+        println!("{}", format_status(&statuses));
+        return;
+    }
+
+    let component = cli
+        .component
+        .as_deref()
+        .map(|name| config.component(name).unwrap_or_else(|e| abort(&e)));
+    let config = match &component {
+        Some(component) => {
+            info!("Component: {}", component.name);
+            config.for_component(component)
+        }
+        None => config,
+    };
+
+    let release_branches = config.release_branches(default_branch(&repo).as_deref());
+    let policy = release_branch_policy(&repo, &release_branches);
+    if !cli.force && policy.is_none() {
+        let patterns: Vec<&str> = release_branches
+            .iter()
+            .map(|policy| policy.pattern.as_str())
+            .collect();
+        abort(&format!(
+            "No release branch ({}) checked out, aborting.",
+            patterns.join(", ")
+        ));
+    }
+
+    if !cli.allow_dirty {
+        check_clean_working_tree(&repo).unwrap_or_else(|e| abort(&e));
+    }
+    if !cli.allow_unsynced {
+        check_upstream_in_sync(&repo).unwrap_or_else(|e| abort(&e));
+    }
+
+    let interactive = is_interactive();
+    if let Some(Command::Workspace) = cli.command {
+        tag_workspace(&cli, &repo, &config, policy, selection, interactive);
         return;
     }
 
-    let last_tag = find_latest_semver_tag(&repo).expect("Error with tags");
+    let tag_filter = config.tag_filter().unwrap_or_else(|e| abort(&e));
+    let last_tag = find_latest_semver_tag(&repo, &tag_filter, selection)
+        .unwrap_or_else(|e| abort(&format!("Could not look up tags: {}", e)));
+    let paths = component
+        .map(|component| component.paths)
+        .unwrap_or_default();
+    let commits = commits_since(&repo, last_tag.as_deref())
+        .and_then(|commits| commits_touching(&repo, commits, &paths))
+        .unwrap_or_else(|e| abort(&format!("Could not read commits: {}", e)));
+    if !cli.allow_empty {
+        check_head_untagged(&repo, &tag_filter).unwrap_or_else(|e| abort(&e));
+        if let Some(last_tag) = &last_tag {
+            check_new_commits(last_tag, commits.len()).unwrap_or_else(|e| abort(&e));
+        }
+    }
+    let (mut version, bump) = match &last_tag {
+        Some(last_tag) => {
+            let (version, bump) = bumped_version(&cli, &config, last_tag, &commits, interactive);
+            (version, Some(bump))
+        }
+        None => (initial_version(&cli, &config, interactive), None),
+    };
+    if let Some(prefix) = &config.tag_prefix {
+        version.prefix = prefix.clone();
+    }
+    match policy {
+        Some(policy) if !cli.force => policy.check(bump, &version).unwrap_or_else(|e| abort(&e)),
+        _ => (),
+    }
+    let new_tag = version.to_string();
+    check_tag_available(&repo, &new_tag).unwrap_or_else(|e| abort(&e));
+
+    let mut tag = prepare_new_tag(&repo, &new_tag)
+        .unwrap_or_else(|e| abort(&format!("Could not prepare new tag: {}", e)));
+    if cli.sign || cli.no_sign {
+        tag.sign = cli.sign;
+    } else if let Some(sign) = config.sign {
+        tag.sign = sign;
+    }
 
-    let (tag_prefix, mut major, mut minor, mut patch, tag_suffix) = split_tag_semver(&last_tag)
-        .unwrap_or_else(|| panic!("Version could not be found in tag: {}", last_tag));
+    let context = MessageContext {
+        version: new_tag.clone(),
+        previous: last_tag.clone(),
+        bump,
+        commits: commits
+            .iter()
+            .map(|commit| commit.summary().unwrap_or_default().to_owned())
+            .collect(),
+        date: MessageContext::format_date(&tag.tagger.when()),
+    };
+    let template = cli
+        .message
+        .as_deref()
+        .or(config.message.as_deref())
+        .unwrap_or(DEFAULT_MESSAGE_TEMPLATE);
+    tag.message = render_message(template, &context);
+    if cli.edit {
+        if !interactive {
+            abort("Not running in a terminal, the tag message cannot be edited.");
+        }
+        let config = repo
+            .config()
+            .unwrap_or_else(|e| abort(&format!("Could not read git configuration: {}", e)));
+        tag.message = edit_message(&config, &tag.message).unwrap_or_else(|e| abort(&e));
+    }
 
-    info!("Last tagged version: {}.{}.{}", major, minor, patch);
+    let manifests = match cli.manifest.is_empty() {
+        true => config.manifests.clone().unwrap_or_default(),
+        false => cli.manifest.clone(),
+    };
+    let replacements = config.replacements.clone().unwrap_or_default();
+    let changelog = cli.changelog.as_deref().or(config.changelog.as_deref());
+    let updates = match repo.workdir() {
+        Some(work_dir) => {
+            let bare_version = Version {
+                prefix: String::new(),
+                ..version.clone()
+            }
+            .to_string();
+            let mut updates =
+                update_manifests(&repo, &manifests, &bare_version).unwrap_or_else(|e| abort(&e));
+            apply_replacements(
+                work_dir,
+                &mut updates,
+                &replacements,
+                &bare_version,
+                &new_tag,
+            )
+            .unwrap_or_else(|e| abort(&e));
+            if let Some(changelog) = changelog {
+                let mut commits = commits.clone();
+                if let Some(skip_tags) = config.skip_tags().unwrap_or_else(|e| abort(&e)) {
+                    commits = commits_outside_tags(&repo, commits, &skip_tags)
+                        .unwrap_or_else(|e| abort(&format!("Could not read commits: {}", e)));
+                }
+                let messages: Vec<String> = commits
+                    .iter()
+                    .rev()
+                    .map(|commit| commit.message().unwrap_or_default().to_owned())
+                    .collect();
+                let groups = CommitRules::new(&config)
+                    .unwrap_or_else(|e| abort(&e))
+                    .group_commits(&messages);
+                update_changelog(
+                    work_dir,
+                    &mut updates,
+                    changelog,
+                    &bare_version,
+                    &context.date,
+                    &groups,
+                )
+                .unwrap_or_else(|e| abort(&e));
+            }
+            updates.retain(|update| update.old != update.new);
+            updates
+        }
+        None if manifests.is_empty() && replacements.is_empty() && changelog.is_none() => {
+            Vec::new()
+        }
+        None => abort("Files cannot be updated in a bare repository."),
+    };
+    let commit_message = render_message(
+        config
+            .commit_message
+            .as_deref()
+            .unwrap_or(DEFAULT_COMMIT_MESSAGE_TEMPLATE),
+        &context,
+    );
 
-    let bump = prompt_bump_element();
+    let push = push_remote(&cli, &config);
 
-    semver_bump(&mut major, &mut minor, &mut patch, &bump);
-    let new_tag = format!("{}{}.{}.{}{}", tag_prefix, major, minor, patch, tag_suffix);
+    if !updates.is_empty() {
+        let diff: Vec<String> = updates.iter().map(|update| update.diff()).collect();
+        info!(
+            "Release commit \"{}\" with the following changes:\n{}",
+            commit_message,
+            diff.join("").trim_end()
+        );
+    }
 
-    create_new_tag(&repo, &new_tag).expect("Could not create new tag.");
+    if cli.dry_run {
+        info!("Dry run, the following tag would be created:\n{}", tag);
+        if let Some(remote) = &push {
+            info!("Dry run, the tag would be pushed to {}", remote);
+        }
+        return;
+    }
+
+    if requires_confirmation(cli.yes, interactive).unwrap_or_else(|e| abort(&e)) {
+        let confirmed = confirm_tag_creation(&new_tag)
+            .unwrap_or_else(|e| abort(&format!("Could not read confirmation: {}", e)));
+        if !confirmed {
+            info!("Aborting.");
+            return;
+        }
+    }
+
+    let release_branch = create_release_tags(
+        &repo,
+        std::slice::from_mut(&mut tag),
+        &updates,
+        &commit_message,
+    );
+
+    if let Some(remote) = &push {
+        push_tag(&repo, remote, &new_tag, release_branch.as_deref()).unwrap_or_else(|e| {
+            abort(&format!(
+                "Could not push tag {} to {}, it is only available locally: {}",
+                new_tag,
+                remote,
+                e.message()
+            ))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        let cli = Cli::try_parse_from(std::iter::once("taggr").chain(args.iter().copied()))?;
+        check_arguments(&cli)?;
+        Ok(cli)
+    }
+
+    #[test]
+    fn test_release_conflicts_with_pre() {
+        let error = parse(&["--bump", "release", "--pre", "rc"]).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::ArgumentConflict);
+        assert!(parse(&["--bump", "minor", "--pre", "rc"]).is_ok());
+        assert!(parse(&["--bump", "release"]).is_ok());
+    }
+
+    #[test]
+    fn test_workspace_rejects_unsupported_arguments() {
+        for args in [
+            &["--changelog", "workspace"][..],
+            &["--manifest", "Cargo.toml", "workspace"],
+            &["--component", "api", "workspace"],
+            &["--pre", "rc", "workspace"],
+            &["--edit", "workspace"],
+            &["--initial", "v0.1.0", "workspace"],
+        ] {
+            let error = parse(args).err().unwrap();
+            assert_eq!(error.kind(), ErrorKind::ArgumentConflict, "{:?}", args);
+        }
+        // Non-global arguments after the subcommand are rejected by clap itself
+        assert!(parse(&["workspace", "--pre", "rc"]).is_err());
+        assert!(parse(&["--bump", "minor", "workspace", "--yes"]).is_ok());
+        assert!(parse(&["--changelog"]).is_ok());
+    }
 }
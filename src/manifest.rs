@@ -0,0 +1,553 @@
+use git2::Repository;
+use log::debug;
+use regex::{Captures, Regex};
+use std::path::Path;
+
+use crate::cargo::bump_manifests;
+use crate::config::Replacement;
+
+/// Matches a TOML table header, e.g. `[package]`.
+const TOML_HEADER_REGEX: &str = r"^\s*\[([^\[\]]+)\]\s*(?:#.*)?$";
+
+/// Matches a TOML `version` key with a quoted string value.
+const TOML_VERSION_REGEX: &str = r#"^(\s*version\s*=\s*)(["'])[^"']*(["'].*)$"#;
+
+/// Project manifests whose version can be updated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestKind {
+    /// `Cargo.toml`, `[package]` or `[workspace.package]` version
+    Cargo,
+    /// `package.json`, top-level `version`
+    Npm,
+    /// `pyproject.toml`, `[project]` or `[tool.poetry]` version
+    PyProject,
+    /// Helm `Chart.yaml`, `version` and `appVersion` if present
+    Helm,
+    /// Plain `VERSION` file only containing the version
+    Plain,
+}
+
+impl ManifestKind {
+    /// Detect the kind of manifest from its file name.
+    pub fn detect(path: &Path) -> Result<Self, String> {
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some("Cargo.toml") => Ok(ManifestKind::Cargo),
+            Some("package.json") => Ok(ManifestKind::Npm),
+            Some("pyproject.toml") => Ok(ManifestKind::PyProject),
+            Some("Chart.yaml") | Some("Chart.yml") => Ok(ManifestKind::Helm),
+            Some("VERSION") => Ok(ManifestKind::Plain),
+            _ => Err(format!("Unsupported manifest {}.", path.display())),
+        }
+    }
+
+    /// Replace the version in the content of a manifest, preserving its formatting.
+    ///
+    /// * `content`: Content of the manifest
+    /// * `version`: New version without tag prefix
+    pub fn set_version(&self, content: &str, version: &str) -> Result<String, String> {
+        let updated = match self {
+            ManifestKind::Cargo => {
+                set_toml_version(content, &["package", "workspace.package"], version)
+            }
+            ManifestKind::PyProject => {
+                set_toml_version(content, &["project", "tool.poetry"], version)
+            }
+            ManifestKind::Npm => set_json_version(content, version),
+            ManifestKind::Helm => set_yaml_version(content, "version", version).map(|content| {
+                set_yaml_version(&content, "appVersion", version).unwrap_or(content)
+            }),
+            ManifestKind::Plain => {
+                let trimmed = content.trim_end();
+                Some(format!("{}{}", version, &content[trimmed.len()..]))
+            }
+        };
+        updated.ok_or_else(|| "No version found".to_owned())
+    }
+}
+
+/// A file whose content is changed for a release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileUpdate {
+    /// Path relative to the repository root
+    pub path: String,
+    /// Current content
+    pub old: String,
+    /// Content after the release
+    pub new: String,
+}
+
+/// Compute the updates of the version in the given manifests. Cargo manifests are updated
+/// together with their workspace, see `cargo::bump_manifests`. Fails if a manifest is not
+/// supported, cannot be read or does not contain a version.
+///
+/// * `repo`: Repository of the manifests
+/// * `manifests`: Paths of the manifests relative to the working directory
+/// * `version`: New version without tag prefix
+pub fn update_manifests(
+    repo: &Repository,
+    manifests: &[String],
+    version: &str,
+) -> Result<Vec<FileUpdate>, String> {
+    let work_dir = repo
+        .workdir()
+        .ok_or("Manifests cannot be updated in a bare repository.")?;
+    let mut updates = Vec::new();
+    let mut cargo_manifests = Vec::new();
+    for manifest in manifests {
+        let kind = ManifestKind::detect(Path::new(manifest))?;
+        if kind == ManifestKind::Cargo {
+            cargo_manifests.push(manifest.clone());
+            continue;
+        }
+        let update = pending_update(work_dir, &mut updates, manifest)?;
+        update.new = kind
+            .set_version(&update.new, version)
+            .map_err(|e| format!("{} in {}.", e, manifest))?;
+    }
+    if !cargo_manifests.is_empty() {
+        bump_manifests(repo, &mut updates, &cargo_manifests, version)?;
+    }
+    Ok(updates)
+}
+
+/// The pending update of the given file, created from the file's current content if the file is
+/// not updated yet. Allows several edits of the same file to build on each other.
+///
+/// * `work_dir`: Root of the repository's working directory
+/// * `updates`: Pending file updates
+/// * `path`: Path of the file relative to the working directory
+pub fn pending_update<'u>(
+    work_dir: &Path,
+    updates: &'u mut Vec<FileUpdate>,
+    path: &str,
+) -> Result<&'u mut FileUpdate, String> {
+    let index = match updates.iter().position(|update| update.path == path) {
+        Some(index) => index,
+        None => {
+            let old = std::fs::read_to_string(work_dir.join(path))
+                .map_err(|e| format!("Could not read {}: {}", path, e))?;
+            updates.push(FileUpdate {
+                path: path.to_owned(),
+                new: old.clone(),
+                old,
+            });
+            updates.len() - 1
+        }
+    };
+    Ok(&mut updates[index])
+}
+
+impl FileUpdate {
+    /// Render the changed lines as unified diff without context lines.
+    pub fn diff(&self) -> String {
+        let old: Vec<&str> = self.old.lines().collect();
+        let new: Vec<&str> = self.new.lines().collect();
+        let mut diff = format!("--- a/{0}\n+++ b/{0}\n", self.path);
+
+        // Only the lines between the common prefix and suffix need to be compared, which keeps
+        // the comparison small for prepended changelog sections and single version bumps
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let old = &old[prefix..old.len() - suffix];
+        let new = &new[prefix..new.len() - suffix];
+
+        // Longest common subsequence of lines, lengths of the suffixes starting at (i, j)
+        let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lcs[i][j] = if old[i] == new[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() || j < new.len() {
+            if i < old.len() && j < new.len() && old[i] == new[j] {
+                i += 1;
+                j += 1;
+                continue;
+            }
+            let (start_old, start_new) = (i, j);
+            while i < old.len() || j < new.len() {
+                if i < old.len() && j < new.len() && old[i] == new[j] {
+                    break;
+                } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+                    j += 1;
+                } else {
+                    i += 1;
+                }
+            }
+            // An empty range starts at the line before it, like `@@ -2,0 +3,2 @@`
+            let start = |start: usize, len: usize| match len {
+                0 => prefix + start,
+                _ => prefix + start + 1,
+            };
+            diff.push_str(&format!(
+                "@@ -{},{} +{},{} @@\n",
+                start(start_old, i - start_old),
+                i - start_old,
+                start(start_new, j - start_new),
+                j - start_new
+            ));
+            for line in &old[start_old..i] {
+                diff.push_str(&format!("-{}\n", line));
+            }
+            for line in &new[start_new..j] {
+                diff.push_str(&format!("+{}\n", line));
+            }
+        }
+        diff
+    }
+}
+
+/// Apply version replacements to the files of the repository. Files already updated are changed
+/// further, so several replacements and manifests may target the same file. Fails if a regex does
+/// not match at all.
+///
+/// * `work_dir`: Root of the repository's working directory
+/// * `updates`: Pending file updates, extended by the replacements
+/// * `replacements`: Replacements to apply
+/// * `version`: New version without tag prefix
+/// * `tag_name`: Name of the new tag
+pub fn apply_replacements(
+    work_dir: &Path,
+    updates: &mut Vec<FileUpdate>,
+    replacements: &[Replacement],
+    version: &str,
+    tag_name: &str,
+) -> Result<(), String> {
+    for replacement in replacements {
+        let re = Regex::new(&replacement.search_regex)
+            .map_err(|e| format!("Invalid search_regex for {}: {}", replacement.file, e))?;
+        let update = pending_update(work_dir, updates, &replacement.file)?;
+        let matches = re.find_iter(&update.new).count();
+        if matches == 0 {
+            return Err(format!(
+                "{} does not match anything in {}.",
+                replacement.search_regex, replacement.file
+            ));
+        }
+        // Capture groups are expanded first, so the substituted values are never read as ones
+        update.new = re
+            .replace_all(&update.new, |captures: &Captures| {
+                let mut expanded = String::new();
+                captures.expand(&replacement.replace_template, &mut expanded);
+                expanded
+                    .replace("{version}", version)
+                    .replace("{tag}", tag_name)
+            })
+            .into_owned();
+        debug!(
+            "Replaced {} match(es) of {} in {}",
+            matches, replacement.search_regex, replacement.file
+        );
+    }
+    Ok(())
+}
+
+/// Replace the first `version` key in one of the given TOML tables.
+pub fn set_toml_version(content: &str, tables: &[&str], version: &str) -> Option<String> {
+    // Safety: Regexes are verified to be valid
+    let header = Regex::new(TOML_HEADER_REGEX).unwrap();
+    let key = Regex::new(TOML_VERSION_REGEX).unwrap();
+
+    let mut table = String::new();
+    let mut replaced = false;
+    let mut updated = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\r', '\n']);
+        if let Some(captures) = header.captures(text) {
+            table = captures[1].trim().to_owned();
+        } else if !replaced && tables.contains(&table.as_str()) {
+            if let Some(captures) = key.captures(text) {
+                updated.push_str(&captures[1]);
+                updated.push_str(&captures[2]);
+                updated.push_str(version);
+                updated.push_str(&captures[3]);
+                updated.push_str(&line[text.len()..]);
+                replaced = true;
+                continue;
+            }
+        }
+        updated.push_str(line);
+    }
+    replaced.then_some(updated)
+}
+
+/// Replace the value of a top-level YAML key, keeping quotes and trailing comments.
+fn set_yaml_version(content: &str, key: &str, version: &str) -> Option<String> {
+    // Safety: Regex is verified to be valid
+    let re = Regex::new(&format!(
+        r#"(?m)^({}:[ \t]*)(["']?)[^"'\s#]*(["']?)"#,
+        regex::escape(key)
+    ))
+    .unwrap();
+    let captures = re.captures(content)?;
+    let value = captures.get(0)?;
+    Some(format!(
+        "{}{}{}{}{}{}",
+        &content[..value.start()],
+        &captures[1],
+        &captures[2],
+        version,
+        &captures[3],
+        &content[value.end()..]
+    ))
+}
+
+/// Replace the top-level `version` string of a JSON document.
+fn set_json_version(content: &str, version: &str) -> Option<String> {
+    let bytes = content.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth -= 1,
+            b'"' => {
+                let end = json_string_end(bytes, i)?;
+                if depth == 1 && &content[i + 1..end] == "version" {
+                    let value = content[end + 1..].trim_start().strip_prefix(':')?;
+                    let value = value.trim_start();
+                    if !value.starts_with('"') {
+                        return None;
+                    }
+                    let start = content.len() - value.len();
+                    let value_end = json_string_end(bytes, start)?;
+                    return Some(format!(
+                        "{}\"{}\"{}",
+                        &content[..start],
+                        version,
+                        &content[value_end + 1..]
+                    ));
+                }
+                i = end;
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Index of the quote closing the JSON string starting at the given index.
+fn json_string_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'"' => return Some(i),
+            _ => (),
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            ManifestKind::detect(Path::new("crates/cli/Cargo.toml")),
+            Ok(ManifestKind::Cargo)
+        );
+        assert_eq!(
+            ManifestKind::detect(Path::new("charts/app/Chart.yaml")),
+            Ok(ManifestKind::Helm)
+        );
+        assert!(ManifestKind::detect(Path::new("setup.py")).is_err());
+    }
+
+    #[test]
+    fn test_cargo_version() {
+        let content = "[package]\nname = \"taggr\"\nversion = \"0.1.0\" # keep\nedition = \"2021\"\n\n[dependencies]\nlog = { version = \"0.4\" }\n";
+
+        assert_eq!(
+            ManifestKind::Cargo.set_version(content, "0.2.0").unwrap(),
+            "[package]\nname = \"taggr\"\nversion = \"0.2.0\" # keep\nedition = \"2021\"\n\n[dependencies]\nlog = { version = \"0.4\" }\n"
+        );
+    }
+
+    #[test]
+    fn test_cargo_version_missing() {
+        let content = "[package]\nname = \"member\"\nversion.workspace = true\n\n[dependencies]\nversion = \"1\"\n";
+
+        assert!(ManifestKind::Cargo.set_version(content, "0.2.0").is_err());
+    }
+
+    #[test]
+    fn test_pyproject_version() {
+        let content = "[build-system]\nrequires = ['hatchling']\r\n\r\n[project]\r\nname = 'app'\r\nversion = '1.0.0'\r\n";
+
+        assert_eq!(
+            ManifestKind::PyProject.set_version(content, "1.1.0").unwrap(),
+            "[build-system]\nrequires = ['hatchling']\r\n\r\n[project]\r\nname = 'app'\r\nversion = '1.1.0'\r\n"
+        );
+    }
+
+    #[test]
+    fn test_npm_version() {
+        let content = "{\n  \"name\": \"app\",\n  \"engines\": { \"version\": \"18\" },\n  \"description\": \"a \\\"version\\\"\",\n  \"version\" : \"1.0.0\",\n  \"private\": true\n}\n";
+
+        assert_eq!(
+            ManifestKind::Npm.set_version(content, "2.0.0-rc.1").unwrap(),
+            "{\n  \"name\": \"app\",\n  \"engines\": { \"version\": \"18\" },\n  \"description\": \"a \\\"version\\\"\",\n  \"version\" : \"2.0.0-rc.1\",\n  \"private\": true\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_helm_version() {
+        let content = "apiVersion: v2\nname: app\nversion: 0.3.0 # chart\nappVersion: \"0.3.0\"\ndependencies:\n  - name: redis\n    version: 17.0.0\n";
+
+        assert_eq!(
+            ManifestKind::Helm.set_version(content, "0.4.0").unwrap(),
+            "apiVersion: v2\nname: app\nversion: 0.4.0 # chart\nappVersion: \"0.4.0\"\ndependencies:\n  - name: redis\n    version: 17.0.0\n"
+        );
+    }
+
+    fn replacement(file: &str, search_regex: &str, replace_template: &str) -> Replacement {
+        Replacement {
+            file: file.to_owned(),
+            search_regex: search_regex.to_owned(),
+            replace_template: replace_template.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_apply_replacements() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("Dockerfile"),
+            "FROM rust\nLABEL version=\"1.0.0\"\nRUN cargo install app@1.0.0\n",
+        )
+        .unwrap();
+        let mut updates = Vec::new();
+
+        apply_replacements(
+            dir.path(),
+            &mut updates,
+            &[
+                replacement("Dockerfile", r#"version="[^"]*""#, r#"version="{version}""#),
+                replacement("Dockerfile", r"(app)@\S+", "$1@{version} # {tag}"),
+            ],
+            "1.1.0",
+            "v1.1.0",
+        )
+        .unwrap();
+
+        assert_eq!(updates.len(), 1);
+        assert_eq!(
+            updates[0].new,
+            "FROM rust\nLABEL version=\"1.1.0\"\nRUN cargo install app@1.1.0 # v1.1.0\n"
+        );
+        assert_eq!(
+            updates[0].diff(),
+            "--- a/Dockerfile\n+++ b/Dockerfile\n\
+             @@ -2,2 +2,2 @@\n\
+             -LABEL version=\"1.0.0\"\n\
+             -RUN cargo install app@1.0.0\n\
+             +LABEL version=\"1.1.0\"\n\
+             +RUN cargo install app@1.1.0 # v1.1.0\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_replacements_capture_before_version() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("install.sh"), "VERSION=v1.0.0\n").unwrap();
+        let mut updates = Vec::new();
+
+        apply_replacements(
+            dir.path(),
+            &mut updates,
+            &[replacement(
+                "install.sh",
+                r"(VERSION=)\S+",
+                "$1{version} ({tag})",
+            )],
+            "1.1.0",
+            "$v1.1.0",
+        )
+        .unwrap();
+
+        assert_eq!(updates[0].new, "VERSION=1.1.0 ($v1.1.0)\n");
+    }
+
+    #[test]
+    fn test_apply_replacements_no_match() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("README.md"), "cargo install app").unwrap();
+
+        let error = apply_replacements(
+            dir.path(),
+            &mut Vec::new(),
+            &[replacement("README.md", r"app@\S+", "app@{version}")],
+            "1.1.0",
+            "v1.1.0",
+        )
+        .unwrap_err();
+        assert!(error.contains("README.md"));
+    }
+
+    #[test]
+    fn test_diff_large_prepend() {
+        let history: String = (0..5000).map(|i| format!("- Entry {}\n", i)).collect();
+        let update = FileUpdate {
+            path: "CHANGELOG.md".to_owned(),
+            old: format!("# Changelog\n\n{}", history),
+            new: format!("# Changelog\n\n## [1.1.0]\n\n{}", history),
+        };
+
+        assert_eq!(
+            update.diff(),
+            "--- a/CHANGELOG.md\n+++ b/CHANGELOG.md\n@@ -2,0 +3,2 @@\n+## [1.1.0]\n+\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_separate_hunks() {
+        let update = FileUpdate {
+            path: "VERSION".to_owned(),
+            old: "a\nb\nc\nd\n".to_owned(),
+            new: "a\nB\nc\nd\ne\n".to_owned(),
+        };
+
+        assert_eq!(
+            update.diff(),
+            "--- a/VERSION\n+++ b/VERSION\n@@ -2,1 +2,1 @@\n-b\n+B\n@@ -4,0 +5,1 @@\n+e\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_file_start() {
+        let update = FileUpdate {
+            path: "VERSION".to_owned(),
+            old: "b\nc\n".to_owned(),
+            new: "a\nb\n".to_owned(),
+        };
+
+        assert_eq!(
+            update.diff(),
+            "--- a/VERSION\n+++ b/VERSION\n@@ -0,0 +1,1 @@\n+a\n@@ -2,1 +2,0 @@\n-c\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_version() {
+        assert_eq!(
+            ManifestKind::Plain.set_version("1.2.3\n", "1.3.0").unwrap(),
+            "1.3.0\n"
+        );
+    }
+}
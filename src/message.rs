@@ -0,0 +1,161 @@
+use git2::{Config, Time};
+use std::io::Write;
+use std::process::Command;
+use tempfile::NamedTempFile;
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::elements::Type;
+
+/// Tag message used if no template is configured.
+pub const DEFAULT_MESSAGE_TEMPLATE: &str = "Tag created by taggr";
+
+/// Release commit message used if no template is configured.
+pub const DEFAULT_COMMIT_MESSAGE_TEMPLATE: &str = "chore(release): {version}";
+
+/// Values available as placeholders in a tag message template.
+pub struct MessageContext {
+    /// New version, available as `{version}`
+    pub version: String,
+    /// Previous version, available as `{previous}`
+    pub previous: Option<String>,
+    /// Selected bump, available as `{bump}`
+    pub bump: Option<Type>,
+    /// Subjects of the commits since the previous version, available as `{commits}` and
+    /// `{commit_count}`
+    pub commits: Vec<String>,
+    /// Date of the tag, available as `{date}`
+    pub date: String,
+}
+
+impl MessageContext {
+    /// Format the given git time as `YYYY-MM-DD` in its own time zone.
+    pub fn format_date(when: &Time) -> String {
+        let offset =
+            UtcOffset::from_whole_seconds(when.offset_minutes() * 60).unwrap_or(UtcOffset::UTC);
+        OffsetDateTime::from_unix_timestamp(when.seconds())
+            .map(|datetime| datetime.to_offset(offset).date().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Replace the placeholders in the given template. `{commits}` expands to a bullet list of commit
+/// subjects, unknown placeholders are left untouched.
+///
+/// # Example
+/// ```
+/// let message = render_message("Release {version}", &context);
+/// assert_eq!(message, "Release v1.3.0");
+/// ```
+pub fn render_message(template: &str, context: &MessageContext) -> String {
+    let commits: Vec<String> = context
+        .commits
+        .iter()
+        .map(|subject| format!("- {}", subject))
+        .collect();
+
+    template
+        .replace("{version}", &context.version)
+        .replace("{previous}", context.previous.as_deref().unwrap_or("none"))
+        .replace(
+            "{bump}",
+            &context
+                .bump
+                .map(|bump| bump.to_string())
+                .unwrap_or_else(|| "Initial".to_owned()),
+        )
+        .replace("{commit_count}", &context.commits.len().to_string())
+        .replace("{date}", &context.date)
+        .replace("{commits}", &commits.join("\n"))
+}
+
+/// Open the editor configured in git (`core.editor`), `$VISUAL` or `$EDITOR` with the given
+/// message and return the edited message.
+///
+/// * `config`: Git configuration to read the editor from
+/// * `message`: Initial message
+pub fn edit_message(config: &Config, message: &str) -> Result<String, String> {
+    let editor = config
+        .get_string("core.editor")
+        .ok()
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .unwrap_or_else(|| "vi".to_owned());
+
+    let mut file = NamedTempFile::new().map_err(|e| e.to_string())?;
+    file.write_all(message.as_bytes())
+        .map_err(|e| e.to_string())?;
+
+    // Run through the shell as the editor setting may contain arguments
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(&editor)
+        .arg(file.path())
+        .status()
+        .map_err(|e| format!("Could not run editor {}: {}", editor, e))?;
+    if !status.success() {
+        return Err(format!("Editor {} exited with {}", editor, status));
+    }
+
+    let edited = std::fs::read_to_string(file.path()).map_err(|e| e.to_string())?;
+    if edited.trim().is_empty() {
+        return Err("Empty tag message, aborting.".to_owned());
+    }
+    Ok(edited)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> MessageContext {
+        MessageContext {
+            version: "v1.3.0".to_owned(),
+            previous: Some("v1.2.3".to_owned()),
+            bump: Some(Type::Minor),
+            commits: vec!["feat: add templates".to_owned(), "fix: typo".to_owned()],
+            date: "2023-09-14".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_render_message_placeholders() {
+        let message = render_message(
+            "{version} ({bump} after {previous}), {commit_count} commits on {date}",
+            &context(),
+        );
+
+        assert_eq!(
+            message,
+            "v1.3.0 (Minor after v1.2.3), 2 commits on 2023-09-14"
+        );
+    }
+
+    #[test]
+    fn test_render_message_commit_list() {
+        let message = render_message("Release {version}\n\n{commits}", &context());
+
+        assert_eq!(
+            message,
+            "Release v1.3.0\n\n- feat: add templates\n- fix: typo"
+        );
+    }
+
+    #[test]
+    fn test_render_message_unknown_placeholder() {
+        assert_eq!(render_message("{unknown}", &context()), "{unknown}");
+    }
+
+    #[test]
+    fn test_format_date() {
+        // 2023-09-14 23:30 UTC is already the next day in UTC+2
+        assert_eq!(
+            MessageContext::format_date(&Time::new(1694734200, 0)),
+            "2023-09-14"
+        );
+        assert_eq!(
+            MessageContext::format_date(&Time::new(1694734200, 120)),
+            "2023-09-15"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use git2::{Branch, Repository, StatusOptions};
+use log::debug;
+
+use crate::elements::TagFilter;
+use crate::functions::semver_tags;
+
+/// Maximum number of paths listed when the working tree is dirty.
+const MAX_LISTED_PATHS: usize = 5;
+
+/// Check that the working tree has no modified, staged or untracked files. Bare repositories are
+/// always considered clean.
+///
+/// * `repo`: Repository to check
+pub fn check_clean_working_tree(repo: &Repository) -> Result<(), String> {
+    if repo.is_bare() {
+        return Ok(());
+    }
+
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true).include_ignored(false);
+    let statuses = repo
+        .statuses(Some(&mut opts))
+        .map_err(|e| format!("Could not read repository status: {}", e))?;
+    if statuses.is_empty() {
+        return Ok(());
+    }
+
+    let mut paths: Vec<String> = statuses
+        .iter()
+        .take(MAX_LISTED_PATHS)
+        .map(|entry| entry.path().unwrap_or_default().to_owned())
+        .collect();
+    if statuses.len() > MAX_LISTED_PATHS {
+        paths.push(format!("and {} more", statuses.len() - MAX_LISTED_PATHS));
+    }
+    Err(format!(
+        "Working tree has uncommitted changes ({}), commit or stash them or pass --allow-dirty.",
+        paths.join(", ")
+    ))
+}
+
+/// Check that the checked out branch is neither ahead nor behind its upstream branch. Branches
+/// without upstream and detached HEADs are not checked.
+///
+/// * `repo`: Repository to check
+pub fn check_upstream_in_sync(repo: &Repository) -> Result<(), String> {
+    let head = repo
+        .head()
+        .map_err(|e| format!("Could not read HEAD: {}", e))?;
+    if !head.is_branch() {
+        debug!("HEAD is detached, skipping upstream check");
+        return Ok(());
+    }
+
+    let branch = Branch::wrap(head);
+    let name = branch.name().ok().flatten().unwrap_or("HEAD").to_owned();
+    let upstream = match branch.upstream() {
+        Ok(upstream) => upstream,
+        Err(_) => {
+            debug!("Branch {} has no upstream, skipping upstream check", name);
+            return Ok(());
+        }
+    };
+    let upstream_name = upstream
+        .name()
+        .ok()
+        .flatten()
+        .unwrap_or("upstream")
+        .to_owned();
+
+    let (Some(local), Some(remote)) = (branch.get().target(), upstream.get().target()) else {
+        return Ok(());
+    };
+    let (ahead, behind) = repo
+        .graph_ahead_behind(local, remote)
+        .map_err(|e| format!("Could not compare {} to {}: {}", name, upstream_name, e))?;
+    debug!(
+        "{} is {} ahead and {} behind {}",
+        name, ahead, behind, upstream_name
+    );
+
+    match (ahead, behind) {
+        (0, 0) => Ok(()),
+        (ahead, 0) => Err(format!(
+            "{} is {} commit(s) ahead of {}, push first or pass --allow-unsynced.",
+            name, ahead, upstream_name
+        )),
+        (0, behind) => Err(format!(
+            "{} is {} commit(s) behind {}, pull first or pass --allow-unsynced.",
+            name, behind, upstream_name
+        )),
+        (ahead, behind) => Err(format!(
+            "{} has diverged from {} ({} ahead, {} behind), pass --allow-unsynced to tag anyway.",
+            name, upstream_name, ahead, behind
+        )),
+    }
+}
+
+/// Check that HEAD does not already carry a semantic version tag passing the given filter.
+///
+/// * `repo`: Repository to check
+/// * `filter`: Filter of tags considered as releases
+pub fn check_head_untagged(repo: &Repository, filter: &TagFilter) -> Result<(), String> {
+    let head = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(|e| format!("Could not read HEAD: {}", e))?;
+    let tags = semver_tags(repo, filter).map_err(|e| format!("Could not list tags: {}", e))?;
+
+    match tags.iter().find(|(_, _, commit)| *commit == head.id()) {
+        Some((_, tag_name, _)) => Err(format!(
+            "HEAD is already tagged as {}, pass --allow-empty to tag it again.",
+            tag_name
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Check that there are commits since the last release.
+///
+/// * `last_tag`: Name of the tag of the last release
+/// * `commit_count`: Number of commits since the last release
+pub fn check_new_commits(last_tag: &str, commit_count: usize) -> Result<(), String> {
+    if commit_count == 0 {
+        return Err(format!(
+            "0 commits since {}, pass --allow-empty to tag anyway.",
+            last_tag
+        ));
+    }
+    Ok(())
+}
+
+/// Check that no tag with the given name exists yet.
+///
+/// * `repo`: Repository to check
+/// * `tag_name`: Name of the tag to create
+pub fn check_tag_available(repo: &Repository, tag_name: &str) -> Result<(), String> {
+    if repo
+        .find_reference(&format!("refs/tags/{}", tag_name))
+        .is_ok()
+    {
+        return Err(format!("Tag {} already exists.", tag_name));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit, init_repo};
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_clean_working_tree() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+
+        assert!(check_clean_working_tree(&repo).is_ok());
+    }
+
+    #[test]
+    fn test_untracked_file() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        std::fs::write(dir.path().join("notes.txt"), "draft").unwrap();
+
+        let error = check_clean_working_tree(&repo).unwrap_err();
+        assert!(error.contains("notes.txt"));
+    }
+
+    #[test]
+    fn test_staged_file() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        std::fs::write(dir.path().join("VERSION"), "1.0.0").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("VERSION")).unwrap();
+        index.write().unwrap();
+
+        assert!(check_clean_working_tree(&repo).is_err());
+    }
+
+    /// Create a repository whose current branch tracks `origin/<branch>` at the initial commit.
+    fn repo_with_upstream(path: &Path) -> Repository {
+        let repo = init_repo(path);
+        let (branch, target) = {
+            let head = repo.head().unwrap();
+            (head.shorthand().unwrap().to_owned(), head.target().unwrap())
+        };
+        repo.remote("origin", "https://example.com/repo.git")
+            .unwrap();
+        repo.reference(
+            &format!("refs/remotes/origin/{}", branch),
+            target,
+            false,
+            "test",
+        )
+        .unwrap();
+        repo.find_branch(&branch, git2::BranchType::Local)
+            .unwrap()
+            .set_upstream(Some(&format!("origin/{}", branch)))
+            .unwrap();
+        repo
+    }
+
+    #[test]
+    fn test_upstream_in_sync() {
+        let dir = TempDir::new().unwrap();
+        let repo = repo_with_upstream(dir.path());
+
+        assert!(check_upstream_in_sync(&repo).is_ok());
+    }
+
+    #[test]
+    fn test_upstream_ahead() {
+        let dir = TempDir::new().unwrap();
+        let repo = repo_with_upstream(dir.path());
+        commit(&repo, "feat: unpushed");
+
+        let error = check_upstream_in_sync(&repo).unwrap_err();
+        assert!(error.contains("1 commit(s) ahead"));
+    }
+
+    #[test]
+    fn test_no_upstream() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        commit(&repo, "feat: local only");
+
+        assert!(check_upstream_in_sync(&repo).is_ok());
+    }
+
+    #[test]
+    fn test_head_already_tagged() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let head = repo.head().unwrap().peel(git2::ObjectType::Commit).unwrap();
+        repo.tag_lightweight("build-2023", &head, false).unwrap();
+
+        assert!(check_head_untagged(&repo, &TagFilter::new("*")).is_ok());
+
+        repo.tag_lightweight("v1.2.3", &head, false).unwrap();
+        let error = check_head_untagged(&repo, &TagFilter::new("*")).unwrap_err();
+        assert!(error.contains("v1.2.3"));
+
+        commit(&repo, "fix: after release");
+        assert!(check_head_untagged(&repo, &TagFilter::new("*")).is_ok());
+    }
+
+    #[test]
+    fn test_new_commits() {
+        assert!(check_new_commits("v1.0.0", 3).is_ok());
+        assert!(check_new_commits("v1.0.0", 0).is_err());
+    }
+
+    #[test]
+    fn test_tag_available() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let first = repo.head().unwrap().peel(git2::ObjectType::Commit).unwrap();
+        repo.tag_lightweight("v1.1.0", &first, false).unwrap();
+        commit(&repo, "feat: elsewhere");
+
+        assert!(check_tag_available(&repo, "v1.2.0").is_ok());
+        assert_eq!(
+            check_tag_available(&repo, "v1.1.0"),
+            Err("Tag v1.1.0 already exists.".to_owned())
+        );
+    }
+}
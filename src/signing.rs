@@ -0,0 +1,165 @@
+use git2::{Config, Signature};
+use log::debug;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tempfile::NamedTempFile;
+
+/// Signature formats supported by git, configured via `gpg.format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningFormat {
+    OpenPgp,
+    X509,
+    Ssh,
+}
+
+impl SigningFormat {
+    /// Read the signing format from the git configuration, defaulting to OpenPGP.
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        match config.get_string("gpg.format").ok().as_deref() {
+            None | Some("openpgp") => Ok(SigningFormat::OpenPgp),
+            Some("x509") => Ok(SigningFormat::X509),
+            Some("ssh") => Ok(SigningFormat::Ssh),
+            Some(other) => Err(format!("Unsupported gpg.format: {}", other)),
+        }
+    }
+
+    /// The program used to sign, configurable via `gpg.<format>.program`.
+    fn program(&self, config: &Config) -> String {
+        let (key, default) = match self {
+            SigningFormat::OpenPgp => ("gpg.openpgp.program", "gpg"),
+            SigningFormat::X509 => ("gpg.x509.program", "gpgsm"),
+            SigningFormat::Ssh => ("gpg.ssh.program", "ssh-keygen"),
+        };
+        config
+            .get_string(key)
+            .or_else(|_| match self {
+                // `gpg.program` is the historic name of the OpenPGP program setting
+                SigningFormat::OpenPgp => config.get_string("gpg.program"),
+                _ => Err(git2::Error::from_str("not set")),
+            })
+            .unwrap_or_else(|_| default.to_owned())
+    }
+}
+
+/// Returns true if git is configured to sign annotated tags via `tag.gpgSign`.
+pub fn sign_tags_by_default(config: &Config) -> bool {
+    config.get_bool("tag.gpgSign").unwrap_or(false)
+}
+
+/// Serialize a signature the way git writes it into tag and commit objects, e.g.
+/// `Jane Doe <jane@example.com> 1694700000 +0200`.
+pub fn format_signature(signature: &Signature) -> String {
+    let time = signature.when();
+    let offset = time.offset_minutes();
+    format!(
+        "{} <{}> {} {}{:02}{:02}",
+        signature.name().unwrap_or_default(),
+        signature.email().unwrap_or_default(),
+        time.seconds(),
+        if offset < 0 { '-' } else { '+' },
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+/// Sign the given buffer with the key configured in `user.signingkey`, using gpg, gpgsm or
+/// `ssh-keygen -Y sign` depending on `gpg.format`. Returns the armored detached signature.
+///
+/// * `config`: Git configuration to read signing settings from
+/// * `buffer`: Content to sign
+/// * `signer`: Identity used to pick a key if no signing key is configured
+pub fn sign_buffer(config: &Config, buffer: &str, signer: &Signature) -> Result<String, String> {
+    let format = SigningFormat::from_config(config)?;
+    let program = format.program(config);
+    let key = config.get_string("user.signingkey").ok();
+    debug!("Signing with {} ({:?}), key {:?}", program, format, key);
+
+    // Keeps a literal SSH public key alive on disk until signing is done
+    let mut key_file = None;
+    let mut command = Command::new(&program);
+    match format {
+        SigningFormat::OpenPgp | SigningFormat::X509 => {
+            let key = key.unwrap_or_else(|| {
+                format!(
+                    "{} <{}>",
+                    signer.name().unwrap_or_default(),
+                    signer.email().unwrap_or_default()
+                )
+            });
+            command.args(["--status-fd=2", "-bsau", &key]);
+        }
+        SigningFormat::Ssh => {
+            let key = key.ok_or("Signing with SSH requires user.signingkey to be set.")?;
+            command.args(["-Y", "sign", "-n", "git", "-f"]);
+            let literal = key.strip_prefix("key::").unwrap_or(&key);
+            if literal.starts_with("ssh-") || literal.starts_with("ecdsa-") {
+                // A literal public key, the private key is expected in the SSH agent
+                let mut file = NamedTempFile::new().map_err(|e| e.to_string())?;
+                writeln!(file, "{}", literal).map_err(|e| e.to_string())?;
+                command.arg(file.path()).arg("-U");
+                key_file = Some(file);
+            } else {
+                command.arg(expand_home(&key));
+            }
+        }
+    }
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Could not run {}: {}", program, e))?;
+    child
+        .stdin
+        .take()
+        // Safety: stdin is configured to be piped
+        .unwrap()
+        .write_all(buffer.as_bytes())
+        .map_err(|e| format!("Could not write to {}: {}", program, e))?;
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Could not run {}: {}", program, e))?;
+    drop(key_file);
+
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed to sign the tag: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|e| format!("Invalid signature: {}", e))
+}
+
+/// Expand a leading `~/` to the home directory, as git does for `user.signingkey`.
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Time;
+
+    #[test]
+    fn test_format_signature() {
+        let signature =
+            Signature::new("Jane Doe", "jane@example.com", &Time::new(1694700000, 120)).unwrap();
+
+        assert_eq!(
+            format_signature(&signature),
+            "Jane Doe <jane@example.com> 1694700000 +0200"
+        );
+    }
+
+    #[test]
+    fn test_format_signature_negative_offset() {
+        let signature = Signature::new("J", "j@example.com", &Time::new(0, -330)).unwrap();
+
+        assert_eq!(format_signature(&signature), "J <j@example.com> 0 -0530");
+    }
+}
@@ -0,0 +1,219 @@
+use git2::Repository;
+
+use crate::changelog::CommitRules;
+use crate::config::{Component, Config};
+use crate::elements::{TagSelection, Type};
+use crate::functions::{
+    commits_since, commits_touching, find_latest_semver_tag, infer_bump, paths_changed_since,
+};
+
+/// Release state of a monorepo component since its last tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentStatus {
+    /// Name of the component
+    pub name: String,
+    /// Tag of the component's last version
+    pub last_tag: Option<String>,
+    /// Whether files of the component differ between the last tag and HEAD
+    pub changed: bool,
+    /// Subjects of the commits changing files of the component since the last tag
+    pub commits: Vec<String>,
+    /// Suggested bump, None if the component did not change or has no version yet
+    pub bump: Option<Type>,
+}
+
+impl ComponentStatus {
+    /// Determine the release state of a component from its last tag and the commits changing its
+    /// paths since then.
+    ///
+    /// * `repo`: Repository of the component
+    /// * `config`: Configuration of the repository
+    /// * `component`: Component to inspect
+    /// * `selection`: Strategy to select the tag of the last version
+    pub fn read(
+        repo: &Repository,
+        config: &Config,
+        component: &Component,
+        selection: TagSelection,
+    ) -> Result<Self, String> {
+        let scoped = config.for_component(component);
+        let filter = scoped.tag_filter()?;
+        let last_tag = find_latest_semver_tag(repo, &filter, selection)
+            .map_err(|e| format!("Could not look up tags of {}: {}", component.name, e))?;
+        let commits = commits_since(repo, last_tag.as_deref())
+            .and_then(|commits| commits_touching(repo, commits, &component.paths))
+            .map_err(|e| format!("Could not read commits of {}: {}", component.name, e))?;
+        let changed = paths_changed_since(repo, last_tag.as_deref(), &component.paths)
+            .map_err(|e| format!("Could not compare {}: {}", component.name, e))?;
+
+        let bump = match &last_tag {
+            Some(_) if changed => {
+                let rules = CommitRules::new(config)?;
+                infer_bump(&commits, &rules)
+                    .map(|(bump, _)| bump)
+                    .or(config.bump)
+            }
+            _ => None,
+        };
+        Ok(ComponentStatus {
+            name: component.name.clone(),
+            last_tag,
+            changed,
+            commits: commits
+                .iter()
+                .map(|commit| commit.summary().unwrap_or_default().to_owned())
+                .collect(),
+            bump,
+        })
+    }
+}
+
+/// Format the release state of components as table, one line per component.
+pub fn format_status(statuses: &[ComponentStatus]) -> String {
+    let rows: Vec<[String; 5]> = statuses
+        .iter()
+        .map(|status| {
+            [
+                status.name.clone(),
+                status.last_tag.clone().unwrap_or_else(|| "none".to_owned()),
+                if status.changed { "yes" } else { "no" }.to_owned(),
+                status.commits.len().to_string(),
+                match (&status.last_tag, status.bump) {
+                    (None, _) if status.changed || !status.commits.is_empty() => {
+                        "Initial".to_owned()
+                    }
+                    (Some(_), Some(bump)) => bump.to_string(),
+                    _ => "-".to_owned(),
+                },
+            ]
+        })
+        .collect();
+
+    let header = ["Component", "Version", "Changed", "Commits", "Bump"].map(String::from);
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            std::iter::once(&header)
+                .chain(&rows)
+                .map(|row| row[i].len())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    std::iter::once(&header)
+        .chain(&rows)
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect();
+            cells.join("  ").trim_end().to_owned()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit, commit_file, init_repo};
+    use git2::ObjectType;
+    use tempfile::TempDir;
+
+    fn tag_head(repo: &Repository, tag_name: &str) {
+        let head = repo.head().unwrap().peel(ObjectType::Commit).unwrap();
+        repo.tag_lightweight(tag_name, &head, false).unwrap();
+    }
+
+    #[test]
+    fn test_component_status() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "api/main.rs", "fn main() {}", "feat(api): endpoint");
+        tag_head(&repo, "api/v1.2.3");
+        commit_file(&repo, "web/index.html", "<html>", "feat(web): page");
+        tag_head(&repo, "web/v3.0.1");
+        commit_file(
+            &repo,
+            "api/main.rs",
+            "fn main() { run() }",
+            "fix(api): crash",
+        );
+        commit(&repo, "chore: empty");
+        let config = Config::parse(
+            r#"
+            [[components]]
+            name = "api"
+            paths = ["api/"]
+
+            [[components]]
+            name = "web"
+            paths = ["web/"]
+
+            [[components]]
+            name = "docs"
+            paths = ["docs/"]
+            "#,
+        )
+        .unwrap();
+
+        let statuses: Vec<ComponentStatus> = config
+            .components
+            .iter()
+            .flatten()
+            .map(|c| ComponentStatus::read(&repo, &config, c, TagSelection::Nearest).unwrap())
+            .collect();
+
+        assert_eq!(
+            statuses[0],
+            ComponentStatus {
+                name: "api".to_owned(),
+                last_tag: Some("api/v1.2.3".to_owned()),
+                changed: true,
+                commits: vec!["fix(api): crash".to_owned()],
+                bump: Some(Type::Patch),
+            }
+        );
+        assert!(!statuses[1].changed);
+        assert!(statuses[1].commits.is_empty());
+        assert_eq!(statuses[1].bump, None);
+        assert_eq!(statuses[2].last_tag, None);
+        assert!(!statuses[2].changed);
+    }
+
+    #[test]
+    fn test_format_status() {
+        let statuses = [
+            ComponentStatus {
+                name: "api".to_owned(),
+                last_tag: Some("api/v1.2.3".to_owned()),
+                changed: true,
+                commits: vec!["feat: endpoint".to_owned(); 12],
+                bump: Some(Type::Minor),
+            },
+            ComponentStatus {
+                name: "documentation".to_owned(),
+                last_tag: None,
+                changed: true,
+                commits: vec!["docs: intro".to_owned(); 3],
+                bump: None,
+            },
+            ComponentStatus {
+                name: "assets".to_owned(),
+                last_tag: None,
+                changed: false,
+                commits: Vec::new(),
+                bump: None,
+            },
+        ];
+
+        assert_eq!(
+            format_status(&statuses),
+            "Component      Version     Changed  Commits  Bump\n\
+             api            api/v1.2.3  yes      12       Minor\n\
+             documentation  none        yes      3        Initial\n\
+             assets         none        no       0        -"
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use git2::{Commit, Oid, Repository};
+use std::path::Path;
+
+/// Create a repository with an identity and a single empty commit.
+pub fn init_repo(path: &Path) -> Repository {
+    let repo = Repository::init(path).unwrap();
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Taggr Test").unwrap();
+    config.set_str("user.email", "taggr@example.com").unwrap();
+    commit(&repo, "chore: initial commit");
+    repo
+}
+
+/// Create a commit of the current index on HEAD.
+pub fn commit(repo: &Repository, message: &str) -> Oid {
+    let signature = repo.signature().unwrap();
+    let tree_id = repo.index().unwrap().write_tree().unwrap();
+    let tree = repo.find_tree(tree_id).unwrap();
+    let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+    let parents: Vec<&Commit> = parent.iter().collect();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )
+    .unwrap()
+}
+
+/// Write a file relative to the working directory, stage it and commit it on HEAD.
+pub fn commit_file(repo: &Repository, path: &str, content: &str, message: &str) -> Oid {
+    let file = repo.workdir().unwrap().join(path);
+    std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+    std::fs::write(file, content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(path)).unwrap();
+    index.write().unwrap();
+    commit(repo, message)
+}
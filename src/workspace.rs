@@ -0,0 +1,394 @@
+use git2::Repository;
+use log::debug;
+use std::collections::BTreeMap;
+
+use crate::cargo::CargoWorkspace;
+use crate::config::{Component, Config};
+use crate::elements::{TagSelection, Type, Version};
+use crate::functions::semver_bump;
+use crate::status::ComponentStatus;
+
+/// Planned release of a crate of a Cargo workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrateRelease {
+    /// Name of the crate
+    pub name: String,
+    /// Tag of the crate's last version
+    pub previous: Option<String>,
+    /// New version including the tag prefix
+    pub version: Version,
+    /// Applied bump, None for the first release of the crate
+    pub bump: Option<Type>,
+    /// Subjects of the commits changing the crate since its last tag
+    pub commits: Vec<String>,
+    /// Why the crate is released
+    pub reason: String,
+}
+
+/// Reject configuration settings the release of a workspace does not apply, instead of silently
+/// tagging without them.
+///
+/// * `config`: Configuration of the repository
+pub fn check_workspace_config(config: &Config) -> Result<(), String> {
+    let unsupported: Vec<&str> = [
+        ("changelog", config.changelog.is_some()),
+        (
+            "manifests",
+            config.manifests.as_ref().is_some_and(|m| !m.is_empty()),
+        ),
+        (
+            "replacements",
+            config.replacements.as_ref().is_some_and(|r| !r.is_empty()),
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(key, set)| set.then_some(key))
+    .collect();
+    match unsupported.is_empty() {
+        true => Ok(()),
+        false => Err(format!(
+            "The workspace subcommand does not support the configured {}.",
+            unsupported.join(", ")
+        )),
+    }
+}
+
+/// Tag prefix of a crate, e.g. `my-crate-v`.
+pub fn crate_tag_prefix(name: &str) -> String {
+    format!("{}-v", name)
+}
+
+/// The crates of a workspace as components owning the directory of their manifest. A package in
+/// the workspace root owns the whole repository.
+fn crate_components(workspace: &CargoWorkspace) -> Vec<Component> {
+    workspace
+        .packages
+        .iter()
+        .map(|package| Component {
+            tag_prefix: Some(crate_tag_prefix(&package.name)),
+            paths: match package.manifest.rsplit_once('/') {
+                Some((dir, _)) => vec![format!("{}/", dir)],
+                None => Vec::new(),
+            },
+            ..Component::new(&package.name)
+        })
+        .collect()
+}
+
+/// Plan the release of every crate of the workspace that changed since its last tag. Crates
+/// without tag are released with their current version. Dependents of bumped crates get a patch
+/// bump, and crates inheriting the workspace version are bumped together.
+///
+/// * `repo`: Repository of the workspace
+/// * `config`: Configuration of the repository
+/// * `workspace`: Cargo workspace to release
+/// * `selection`: Strategy to select the tag of the last version of each crate
+/// * `bump`: Bump of changed crates, inferred from their commits if None
+pub fn plan_releases(
+    repo: &Repository,
+    config: &Config,
+    workspace: &CargoWorkspace,
+    selection: TagSelection,
+    bump: Option<Type>,
+) -> Result<Vec<CrateRelease>, String> {
+    let components = crate_components(workspace);
+    let config = Config {
+        components: Some(components.clone()),
+        ..config.clone()
+    };
+    let statuses = components
+        .iter()
+        .map(|component| ComponentStatus::read(repo, &config, component, selection))
+        .collect::<Result<Vec<_>, _>>()?;
+    let status = |name: &str| statuses.iter().find(|status| status.name == name);
+
+    // Bump and reason by crate name, a None bump marks a first release
+    let mut bumps: BTreeMap<String, (Option<Type>, String)> = BTreeMap::new();
+    for (package, status) in workspace.packages.iter().zip(&statuses) {
+        match &status.last_tag {
+            None if package.version.is_some() => {
+                bumps.insert(package.name.clone(), (None, "first release".to_owned()));
+            }
+            None => debug!("{} has no version and no tag, skipping", package.name),
+            Some(_) if status.changed => {
+                let reason = format!("{} commit(s)", status.commits.len());
+                let bump = bump.or(status.bump).unwrap_or(Type::Patch);
+                bumps.insert(package.name.clone(), (Some(bump), reason));
+            }
+            Some(_) => (),
+        }
+    }
+
+    loop {
+        let mut changed = false;
+
+        // Dependents get a new requirement on the bumped crate
+        for package in &workspace.packages {
+            if bumps.contains_key(&package.name) {
+                continue;
+            }
+            let dependency = package
+                .dependencies
+                .iter()
+                .find(|dependency| matches!(bumps.get(*dependency), Some((Some(_), _))));
+            if let Some(dependency) = dependency {
+                let reason = format!("depends on {}", dependency);
+                bumps.insert(package.name.clone(), (Some(Type::Patch), reason));
+                changed = true;
+            }
+        }
+
+        // Crates inheriting the workspace version share the most significant bump
+        let inherited = workspace
+            .packages
+            .iter()
+            .filter(|package| package.inherited);
+        let group_bump = inherited
+            .clone()
+            .filter_map(|package| bumps.get(&package.name).and_then(|(bump, _)| *bump))
+            .max();
+        if let Some(group_bump) = group_bump {
+            for package in inherited {
+                let reason = match bumps.get(&package.name) {
+                    Some((Some(bump), _)) if *bump == group_bump => continue,
+                    Some((_, reason)) => reason.clone(),
+                    None => "shares the workspace version".to_owned(),
+                };
+                bumps.insert(package.name.clone(), (Some(group_bump), reason));
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    // Inheriting crates are bumped from the highest version any of them was tagged with
+    let group_base = workspace
+        .packages
+        .iter()
+        .filter(|package| package.inherited)
+        .filter_map(|package| status(&package.name)?.last_tag.as_deref()?.parse().ok())
+        .map(|version: Version| Version {
+            prefix: String::new(),
+            ..version
+        })
+        .max();
+
+    let mut releases = Vec::new();
+    for package in &workspace.packages {
+        let Some((bump, reason)) = bumps.remove(&package.name) else {
+            continue;
+        };
+        // Safety: Every crate has a status
+        let status = status(&package.name).unwrap();
+        let mut version: Version = match (bump, &status.last_tag) {
+            (Some(_), _) if package.inherited => group_base
+                .clone()
+                .or_else(|| package.version.as_deref().and_then(|v| v.parse().ok())),
+            (Some(_), Some(last_tag)) => last_tag.parse().ok(),
+            _ => package.version.as_deref().and_then(|v| v.parse().ok()),
+        }
+        .ok_or_else(|| format!("No valid version found for {}.", package.name))?;
+        if let Some(bump) = &bump {
+            semver_bump(&mut version, bump, None)?;
+        }
+        version.prefix = crate_tag_prefix(&package.name);
+
+        releases.push(CrateRelease {
+            name: package.name.clone(),
+            previous: status.last_tag.clone(),
+            version,
+            bump,
+            commits: status.commits.clone(),
+            reason,
+        });
+    }
+    Ok(releases)
+}
+
+/// Format planned crate releases as table, one line per crate.
+pub fn format_releases(releases: &[CrateRelease]) -> String {
+    let width = releases
+        .iter()
+        .map(|release| release.version.to_string().len())
+        .max()
+        .unwrap_or_default();
+    releases
+        .iter()
+        .map(|release| {
+            let bump = match release.bump {
+                Some(bump) => format!("{} bump", bump),
+                None => "Initial".to_owned(),
+            };
+            format!(
+                "{:width$}  {}, {}",
+                release.version.to_string(),
+                bump,
+                release.reason,
+                width = width
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Replacement;
+    use crate::test_utils::{commit_file, init_repo};
+    use git2::ObjectType;
+    use tempfile::TempDir;
+
+    /// Create a repository with a workspace of `core`, `cli` depending on `core`, and `docs`,
+    /// each tagged at version 0.1.0.
+    fn repo_with_workspace(dir: &TempDir) -> Repository {
+        let repo = init_repo(dir.path());
+        commit_file(
+            &repo,
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+            "chore: workspace",
+        );
+        commit_file(
+            &repo,
+            "crates/core/Cargo.toml",
+            "[package]\nname = \"core\"\nversion = \"0.1.0\"\n",
+            "feat(core): init",
+        );
+        commit_file(
+            &repo,
+            "crates/cli/Cargo.toml",
+            "[package]\nname = \"cli\"\nversion = \"0.1.0\"\n\n\
+             [dependencies]\ncore = { path = \"../core\", version = \"0.1.0\" }\n",
+            "feat(cli): init",
+        );
+        commit_file(
+            &repo,
+            "crates/docs/Cargo.toml",
+            "[package]\nname = \"docs\"\nversion = \"0.1.0\"\n",
+            "docs: init",
+        );
+        {
+            let head = repo.head().unwrap().peel(ObjectType::Commit).unwrap();
+            for name in ["core", "cli", "docs"] {
+                repo.tag_lightweight(&format!("{}-v0.1.0", name), &head, false)
+                    .unwrap();
+            }
+        }
+        repo
+    }
+
+    fn plan(repo: &Repository, dir: &TempDir) -> Vec<CrateRelease> {
+        let workspace = CargoWorkspace::find(dir.path(), "Cargo.toml").unwrap();
+        plan_releases(
+            repo,
+            &Config::default(),
+            &workspace,
+            TagSelection::Nearest,
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_plan_nothing_changed() {
+        let dir = TempDir::new().unwrap();
+        let repo = repo_with_workspace(&dir);
+
+        assert!(plan(&repo, &dir).is_empty());
+    }
+
+    #[test]
+    fn test_plan_propagates_to_dependents() {
+        let dir = TempDir::new().unwrap();
+        let repo = repo_with_workspace(&dir);
+        commit_file(
+            &repo,
+            "crates/core/src/lib.rs",
+            "pub fn run() {}",
+            "feat(core): run",
+        );
+
+        let releases = plan(&repo, &dir);
+        let summary: Vec<(String, Option<Type>, &str)> = releases
+            .iter()
+            .map(|r| (r.version.to_string(), r.bump, r.reason.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "cli-v0.1.1".to_owned(),
+                    Some(Type::Patch),
+                    "depends on core"
+                ),
+                ("core-v0.2.0".to_owned(), Some(Type::Minor), "1 commit(s)"),
+            ]
+        );
+        assert_eq!(releases[1].previous.as_deref(), Some("core-v0.1.0"));
+        assert_eq!(releases[1].commits, vec!["feat(core): run".to_owned()]);
+    }
+
+    #[test]
+    fn test_plan_first_release() {
+        let dir = TempDir::new().unwrap();
+        let repo = repo_with_workspace(&dir);
+        commit_file(
+            &repo,
+            "crates/macros/Cargo.toml",
+            "[package]\nname = \"macros\"\nversion = \"0.3.0\"\n",
+            "feat(macros): init",
+        );
+
+        let releases = plan(&repo, &dir);
+        assert_eq!(releases.len(), 1);
+        assert_eq!(releases[0].version.to_string(), "macros-v0.3.0");
+        assert_eq!(releases[0].bump, None);
+    }
+
+    #[test]
+    fn test_check_workspace_config() {
+        assert!(check_workspace_config(&Config::default()).is_ok());
+
+        let config = Config {
+            changelog: Some("CHANGELOG.md".to_owned()),
+            manifests: Some(Vec::new()),
+            replacements: Some(vec![Replacement {
+                file: "README.md".to_owned(),
+                search_regex: "v[0-9.]+".to_owned(),
+                replace_template: "v{version}".to_owned(),
+            }]),
+            ..Config::default()
+        };
+        assert_eq!(
+            check_workspace_config(&config),
+            Err(
+                "The workspace subcommand does not support the configured changelog, replacements."
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_format_releases() {
+        let release = |version: &str, bump, reason: &str| CrateRelease {
+            name: String::new(),
+            previous: None,
+            version: version.parse().unwrap(),
+            bump,
+            commits: Vec::new(),
+            reason: reason.to_owned(),
+        };
+
+        assert_eq!(
+            format_releases(&[
+                release("cli-v0.1.1", Some(Type::Patch), "depends on core"),
+                release("macros-v0.3.0", None, "first release"),
+            ]),
+            "cli-v0.1.1     Patch bump, depends on core\nmacros-v0.3.0  Initial, first release"
+        );
+    }
+}